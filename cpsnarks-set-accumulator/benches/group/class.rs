@@ -23,6 +23,7 @@ fn criterion_benchmark(c: &mut Criterion) {
     .unwrap(),
   ));
     let right = left.clone();
+    let (compose_left, compose_right) = (left.clone(), right.clone());
 
     // Generator element.
     let base = ClassGroup::elem((
@@ -64,9 +65,16 @@ fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("group_class_op", move |b| {
         b.iter(|| ClassGroup::op(&left, &right))
     });
+    c.bench_function("group_class_compose", move |b| {
+        b.iter(|| ClassGroup::compose(&compose_left, &compose_right))
+    });
+    let (secret_base, secret_exp) = (base.clone(), exp.clone());
     c.bench_function("group_class_exp", move |b| {
         b.iter(|| ClassGroup::exp(&base, &exp))
     });
+    c.bench_function("group_class_exp_secret", move |b| {
+        b.iter(|| ClassGroup::exp_secret(&secret_base, &secret_exp))
+    });
     c.bench_function("group_class_inv", move |b| {
         b.iter(|| ClassGroup::inv(&g_inv))
     });
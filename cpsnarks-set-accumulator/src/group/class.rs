@@ -1,22 +1,149 @@
-//! Fixed-discriminant implementation of an ideal class group, with future optimizations.
+//! Implementation of an ideal class group, with future optimizations.
 //!
 //! Using a class group instead of an RSA group for accumulators or vector commitments eliminates
 //! the need for a trusted setup, albeit at the expense of slower operations.
-use super::{ElemFrom, ElemToBytes, Group, UnknownOrderGroup};
+//!
+//! The discriminant is part of the group type: `Class<D>` takes it from the `DiscriminantSource`
+//! `D`. `ClassGroup` uses a fixed 2048-bit discriminant, while a `SeededDiscriminant` is derived
+//! from a public seed at a chosen size, so each deployment can pick its own group.
+use super::{simul_exp, ElemError, ElemFrom, ElemToBytes, Group, UnknownOrderGroup};
+use crate::hash::{hash, Blake2b};
 use crate::util;
 use crate::util::{int, TypeRep};
+use rug::integer::{IsPrime, Order};
 use rug::{rand::MutRandState, Assign, Integer};
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::UnsafeCell;
+use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem;
 use std::str::FromStr;
+use std::sync::Once;
 
 use rug_binserial::Integer as BinInteger;
 
+/// Miller-Rabin rounds used when deriving a discriminant from a seed.
+const PRIMALITY_REPS: u32 = 30;
+
+/// Largest window width used by `exp`.
+const MAX_WNAF_WIDTH: u32 = 7;
+
 #[allow(clippy::module_name_repetitions)]
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(bound = "")]
 /// Class group implementation, with future optimizations available via the `--features` flag.
-/// Discriminant generated via OpenSSL.
-pub enum ClassGroup {}
+/// The discriminant is supplied by `D`.
+pub struct Class<D: DiscriminantSource>(PhantomData<D>);
+
+/// Class group with the fixed 2048-bit discriminant `CLASS_GROUP_DISCRIMINANT`.
+pub type ClassGroup = Class<Discriminant2048>;
+
+/// Supplies the discriminant of `Class<D>`.
+pub trait DiscriminantSource: Clone + Debug + Eq + Hash + Send + Sync + 'static {
+    /// Returns the discriminant of the group: a negative prime `d` with `d = 1 mod 8`.
+    fn discriminant() -> &'static Integer;
+}
+
+/// A discriminant derived from a public seed with `create_discriminant`.
+///
+/// The discriminant is computed the first time the group is used and cached in the
+/// `DiscriminantCell` of the type, later lookups do not take any lock:
+///
+/// ```ignore
+/// impl SeededDiscriminant for MySeed {
+///     const SEED: &'static [u8] = b"my deployment";
+///     const BITS: u32 = 2048;
+///
+///     fn cell() -> &'static DiscriminantCell {
+///         static CELL: DiscriminantCell = DiscriminantCell::new();
+///         &CELL
+///     }
+/// }
+/// ```
+pub trait SeededDiscriminant: Clone + Debug + Eq + Hash + Send + Sync + 'static {
+    /// The public seed.
+    const SEED: &'static [u8];
+
+    /// Size of the discriminant in bits.
+    const BITS: u32;
+
+    /// Cache of the discriminant, a static of the implementing type.
+    fn cell() -> &'static DiscriminantCell;
+}
+
+impl<S: SeededDiscriminant> DiscriminantSource for S {
+    fn discriminant() -> &'static Integer {
+        S::cell().get_or_create(S::SEED, S::BITS)
+    }
+}
+
+/// Discriminant computed once on first use, see `SeededDiscriminant`.
+pub struct DiscriminantCell {
+    once: Once,
+    value: UnsafeCell<Option<Integer>>,
+}
+
+impl Default for DiscriminantCell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the value is only written once, inside `call_once`, and read after it completed
+unsafe impl Sync for DiscriminantCell {}
+
+impl DiscriminantCell {
+    /// An empty cell.
+    pub const fn new() -> Self {
+        DiscriminantCell {
+            once: Once::new(),
+            value: UnsafeCell::new(None),
+        }
+    }
+
+    fn get_or_create(&'static self, seed: &[u8], bits: u32) -> &'static Integer {
+        self.once.call_once(|| {
+            let discriminant = create_discriminant(seed, bits);
+            unsafe { *self.value.get() = Some(discriminant) };
+        });
+        unsafe { (*self.value.get()).as_ref().unwrap() }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Discriminant source for `ClassGroup`. Discriminant generated via OpenSSL.
+pub enum Discriminant2048 {}
+
+impl DiscriminantSource for Discriminant2048 {
+    fn discriminant() -> &'static Integer {
+        &CLASS_GROUP_DISCRIMINANT
+    }
+}
+
+/// Deterministically derives a discriminant of `bits` bits from `seed`.
+///
+/// Hashes the seed together with a counter until the result, with the top bit set and congruent to
+/// `7 mod 8`, is a probable prime `p`, and returns `-p`. The congruence makes `d = 1 mod 8`, so that
+/// `2` splits and `unknown_order_elem` (a form with `a = 2`) exists.
+pub fn create_discriminant(seed: &[u8], bits: u32) -> Integer {
+    assert!(bits >= 8, "discriminant too small");
+    let blocks = (bits + 255) / 256;
+    let mut counter = 0_u64;
+    loop {
+        let bytes: Vec<u8> = (0..blocks)
+            .flat_map(|block| hash(&Blake2b::default, &(seed, counter, block)).to_vec())
+            .collect();
+        let mut candidate = Integer::from_digits(&bytes, Order::Msf);
+        candidate.keep_bits_mut(bits);
+        candidate.set_bit(bits - 1, true);
+        candidate |= 7;
+        if candidate.is_probably_prime(PRIMALITY_REPS) != IsPrime::No {
+            return -candidate;
+        }
+        counter += 1;
+    }
+}
 
 // 2048-bit prime, negated, congruent to `3 mod 4`. Generated using OpenSSL.
 // According to "A Survey of IQ Cryptography" (Buchmann & Hamdy) Table 1, IQ-MPQS for computing
@@ -37,18 +164,99 @@ lazy_static! {
 }
 
 #[allow(clippy::module_name_repetitions)]
-#[derive(Clone, Debug, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq)]
 /// A class group element, which wraps three GMP integers from the `rug` crate. You should never
 /// need to construct a class group element yourself.
-pub struct ClassElem {
+///
+/// Elements are serialized with their compressed encoding, see `to_bytes`.
+pub struct ClassElem<D: DiscriminantSource = Discriminant2048> {
     a: BinInteger,
     b: BinInteger,
     c: BinInteger,
+    discriminant: PhantomData<D>,
+}
+
+impl<D: DiscriminantSource> ClassElem<D> {
+    fn new(a: Integer, b: Integer, c: Integer) -> Self {
+        ClassElem {
+            a: a.into(),
+            b: b.into(),
+            c: c.into(),
+            discriminant: PhantomData,
+        }
+    }
+
+    // Width in bytes of `a` and `|b|` in the compressed encoding. Reduced elements have
+    // `|b| <= a <= sqrt(|d| / 3)`.
+    fn coordinate_len() -> usize {
+        let bound = (int(D::discriminant().abs_ref()) / 3).sqrt();
+        (bound.significant_bits() as usize + 7) / 8
+    }
+
+    /// Compressed encoding of the element: `c` is dropped, since the discriminant determines it
+    /// from `a` and `b`. The sign of `b` takes one byte, followed by `a` and `|b|` as fixed-width
+    /// big-endian integers, so all elements of the group encode to the same length and every
+    /// element has exactly one encoding. This is two thirds the size of `(a, b, c)` at best.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = Self::coordinate_len();
+        let mut bytes = vec![0u8; 1 + 2 * len];
+        bytes[0] = (*self.b.as_ref() < 0) as u8;
+        write_fixed(self.a.as_ref(), &mut bytes[1..=len]);
+        write_fixed(&int(self.b.as_ref().abs_ref()), &mut bytes[1 + len..]);
+        bytes
+    }
+
+    /// Decodes `to_bytes`. Returns `None` unless `bytes` is the encoding of a reduced element of
+    /// `Class<D>`.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let len = Self::coordinate_len();
+        if bytes.len() != 1 + 2 * len || bytes[0] > 1 {
+            return None;
+        }
+        let a = Integer::from_digits(&bytes[1..=len], Order::MsfBe);
+        let mut b = Integer::from_digits(&bytes[1 + len..], Order::MsfBe);
+        if bytes[0] == 1 {
+            if b == 0 {
+                return None;
+            }
+            b = -b;
+        }
+        if a <= 0 {
+            return None;
+        }
+
+        // c = (b * b - d) / 4a
+        let (c, rem) = (int(b.square_ref()) - D::discriminant()).div_rem(int(4 * &a));
+        if rem != 0 || !Class::<D>::is_reduced(&a, &b, &c) {
+            return None;
+        }
+        Some(Self::new(a, b, c))
+    }
+}
+
+// Writes the non-negative `n` into `bytes` as a big-endian integer, padded with leading zeros.
+fn write_fixed(n: &Integer, bytes: &mut [u8]) {
+    let digits = n.significant_digits::<u8>();
+    let start = bytes.len() - digits;
+    n.write_digits(&mut bytes[start..], Order::MsfBe);
+}
+
+impl<D: DiscriminantSource> Serialize for ClassElem<D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de, D: DiscriminantSource> Deserialize<'de> for ClassElem<D> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Self::from_bytes(&bytes).ok_or_else(|| de::Error::custom("invalid class group element"))
+    }
 }
 
-// `ClassElem` and `ClassGroup` ops based on Chia's fantastic doc explaining applied class groups:
+// `ClassElem` and `Class` ops based on Chia's fantastic doc explaining applied class groups:
 // https://github.com/Chia-Network/vdf-competition/blob/master/classgroups.pdf.
-impl ClassGroup {
+impl<D: DiscriminantSource> Class<D> {
     /// This method is only public for benchmarking. You should not need to use it.
     pub fn normalize(a: Integer, b: Integer, c: Integer) -> (Integer, Integer, Integer) {
         if Self::is_normal(&a, &b, &c) {
@@ -80,57 +288,133 @@ impl ClassGroup {
         Self::normalize(a, b, c)
     }
 
-    #[allow(non_snake_case)]
+    /// NUCOMP: composes `x` and `y` (of discriminant `d`) without reducing, but the partial
+    /// extended Euclidean algorithm keeps the intermediate operands around `|d|^(1/4)` and the
+    /// output close to reduced. See Jacobson and van der Poorten, "Computational aspects of
+    /// NUCOMP" (ANTS 2002).
+    ///
     /// This method is only public for benchmarking. You should not need to use it.
-    pub fn square(x: &ClassElem) -> ClassElem {
-        // Solve `bk = c mod a` for `k`, represented by `mu`, `v` and any integer `n` s.t.
-        // `k = mu + v * n`.
-        let (mu, _) =
-            util::solve_linear_congruence(x.b.as_ref(), x.c.as_ref(), x.a.as_ref()).unwrap();
-
-        // A = a^2
-        // B = b - 2a * mu
-        // tmp = (b * mu) / a
-        // C = mu^2 - tmp
-        let a = int(x.a.as_ref().square_ref());
-        let b = x.b.as_ref() - int(2 * x.a.as_ref()) * &mu;
-        let (tmp, _) = <(Integer, Integer)>::from(
-            int((x.b.as_ref() * &mu) - x.c.as_ref()).div_rem_floor_ref(x.a.as_ref()),
-        );
-        let c = mu.square() - tmp;
+    pub fn nucomp(d: &Integer, x: &ClassElem<D>, y: &ClassElem<D>) -> (Integer, Integer, Integer) {
+        let (x, y) = if x.a.as_ref() > y.a.as_ref() {
+            (y, x)
+        } else {
+            (x, y)
+        };
+        let b2 = y.b.as_ref();
+        let mut a1 = int(x.a.as_ref());
+        let mut a2 = int(y.a.as_ref());
+        let mut c2 = int(y.c.as_ref());
+
+        // s = (b1 + b2) / 2
+        // m = (b1 - b2) / 2
+        let s = (int(x.b.as_ref()) + b2) / 2;
+        let m = (int(x.b.as_ref()) - b2) / 2;
+
+        // Solve `a2 * k = m mod a1` (up to the common factor `sp`), then fold in `s` if `a1` and
+        // `a2` are not coprime.
+        let (sp, v1, _) = <(Integer, Integer, Integer)>::from(a2.gcd_cofactors_ref(&a1));
+        let mut k = rem_euc(m.clone() * v1, &a1);
+        if sp != 1 {
+            let (e, v2, u2) = <(Integer, Integer, Integer)>::from(s.gcd_cofactors_ref(&sp));
+            k = k * u2 - v2 * &c2;
+            if e != 1 {
+                a1 /= &e;
+                a2 /= &e;
+                c2 *= &e;
+            }
+            k = rem_euc(k, &a1);
+        }
 
-        Self::elem((a, b, c))
-    }
+        let bound = Self::nucomp_bound(d);
+        if a1 < bound {
+            // The operands are already small: compose directly.
+            let t = int(&a2 * &k);
+            let a = int(&a2 * &a1);
+            let b = int(2 * &t) + b2;
+            let c = ((t + b2) * &k + &c2) / &a1;
+            return (a, b, c);
+        }
 
-    fn discriminant(a: &Integer, b: &Integer, c: &Integer) -> Integer {
-        int(b.square_ref()) - int(4) * a * c
+        let (co2, co1, r1) = partial_xgcd(a1.clone(), k, &bound);
+        let t = int(&a2 * &r1);
+        let m1 = (int(&m * &co1) + &t) / &a1;
+        let m2 = (int(&s * &r1) - int(&c2 * &co1)) / &a1;
+        let a = if co1 < 0 {
+            int(&r1 * &m1) - int(&co1 * &m2)
+        } else {
+            int(&co1 * &m2) - int(&r1 * &m1)
+        };
+        Self::nucomp_finish(d, a, t, &co1, &co2, b2)
     }
 
-    fn validate(a: &Integer, b: &Integer, c: &Integer) -> bool {
-        Self::discriminant(a, b, c) == *Self::rep()
-    }
+    /// NUDUPL: the specialization of `nucomp` to squaring.
+    ///
+    /// This method is only public for benchmarking. You should not need to use it.
+    pub fn nudupl(d: &Integer, x: &ClassElem<D>) -> (Integer, Integer, Integer) {
+        let b1 = x.b.as_ref();
+        let mut a1 = int(x.a.as_ref());
+        let mut c1 = int(x.c.as_ref());
+
+        // Solve `b1 * k = -c1 mod a1`, up to the common factor `e`.
+        let (e, v2, _) = <(Integer, Integer, Integer)>::from(b1.gcd_cofactors_ref(&a1));
+        if e != 1 {
+            a1 /= &e;
+            c1 *= &e;
+        }
+        let k = rem_euc(-(v2 * &c1), &a1);
+
+        let bound = Self::nucomp_bound(d);
+        if a1 < bound {
+            let t = int(&a1 * &k);
+            let a = int(a1.square_ref());
+            let b = int(2 * &t) + b1;
+            let c = ((t + b1) * &k + &c1) / &a1;
+            return (a, b, c);
+        }
 
-    fn is_reduced(a: &Integer, b: &Integer, c: &Integer) -> bool {
-        Self::is_normal(a, b, c) && (a <= c && !(a == c && *b < int(0)))
+        let (co2, co1, r1) = partial_xgcd(a1.clone(), k, &bound);
+        let t = int(&a1 * &r1);
+        let m2 = (int(b1 * &r1) - int(&c1 * &co1)) / &a1;
+        let a = if co1 < 0 {
+            int(r1.square_ref()) - int(&co1 * &m2)
+        } else {
+            int(&co1 * &m2) - int(r1.square_ref())
+        };
+        Self::nucomp_finish(d, a, t, &co1, &co2, b1)
     }
 
-    fn is_normal(a: &Integer, b: &Integer, _c: &Integer) -> bool {
-        -int(a) < int(b) && b <= a
+    // Common tail of `nucomp` and `nudupl`: computes `b` and `c` from `a` and makes `a` positive.
+    fn nucomp_finish(
+        d: &Integer,
+        a: Integer,
+        t: Integer,
+        co1: &Integer,
+        co2: &Integer,
+        b2: &Integer,
+    ) -> (Integer, Integer, Integer) {
+        // b = (2 * (t - a * co2) / co1 - b2) mod 2a
+        // c = (b^2 - d) / 4a
+        let (b, _) = (int(2) * (t - int(&a * co2))).div_rem_floor(co1.clone());
+        let (_, b) = (b - b2).div_rem_floor(int(2 * &a));
+        let c = (int(b.square_ref()) - d) / int(4 * &a);
+        if a < 0 {
+            (-a, b, -c)
+        } else {
+            (a, b, c)
+        }
     }
-}
 
-impl TypeRep for ClassGroup {
-    type Rep = Integer;
-    fn rep() -> &'static Self::Rep {
-        &CLASS_GROUP_DISCRIMINANT
+    // The partial Euclidean algorithm in `nucomp` stops once the remainder is below
+    // `(|d| / 4)^(1/4)`.
+    fn nucomp_bound(d: &Integer) -> Integer {
+        (int(d.abs_ref()) >> 2).root(4)
     }
-}
-
-impl Group for ClassGroup {
-    type Elem = ClassElem;
 
-    #[allow(non_snake_case)]
-    fn op_(_: &Integer, x: &ClassElem, y: &ClassElem) -> ClassElem {
+    /// Composes `x` and `y` following Chia's doc, with a full reduction afterwards. `op` uses the
+    /// faster `nucomp`; this is kept as a reference.
+    ///
+    /// This method is only public for benchmarking. You should not need to use it.
+    pub fn compose(x: &ClassElem<D>, y: &ClassElem<D>) -> ClassElem<D> {
         // g = (b1 + b2) / 2
         // h = (b2 - b1) / 2
         // w = gcd(a1, a2, g)
@@ -182,95 +466,172 @@ impl Group for ClassGroup {
         Self::elem((a, b, c))
     }
 
+    /// This method is only public for benchmarking. You should not need to use it.
+    pub fn square(x: &ClassElem<D>) -> ClassElem<D> {
+        Self::elem(Self::nudupl(Self::rep(), x))
+    }
+
+    fn discriminant(a: &Integer, b: &Integer, c: &Integer) -> Integer {
+        int(b.square_ref()) - int(4) * a * c
+    }
+
+    fn has_discriminant(a: &Integer, b: &Integer, c: &Integer) -> bool {
+        Self::discriminant(a, b, c) == *Self::rep()
+    }
+
+    fn is_reduced(a: &Integer, b: &Integer, c: &Integer) -> bool {
+        Self::is_normal(a, b, c) && (a <= c && !(a == c && *b < int(0)))
+    }
+
+    fn is_normal(a: &Integer, b: &Integer, _c: &Integer) -> bool {
+        -int(a) < int(b) && b <= a
+    }
+}
+
+impl<D: DiscriminantSource> TypeRep for Class<D> {
+    type Rep = Integer;
+    fn rep() -> &'static Self::Rep {
+        D::discriminant()
+    }
+}
+
+impl<D: DiscriminantSource> Group for Class<D> {
+    type Elem = ClassElem<D>;
+
+    fn op_(d: &Integer, x: &ClassElem<D>, y: &ClassElem<D>) -> ClassElem<D> {
+        Self::elem(Self::nucomp(d, x, y))
+    }
+
     // Constructs the reduced element directly instead of using `Self::Elem()`.
-    fn id_(d: &Integer) -> ClassElem {
+    fn id_(d: &Integer) -> ClassElem<D> {
         let a = int(1);
         let b = int(1);
 
         // c = (b * b - d) / 4a
         let (c, _) = int(1 - d).div_rem_floor(int(4));
-        ClassElem {
-            a: a.into(),
-            b: b.into(),
-            c: c.into(),
-        }
+        ClassElem::new(a, b, c)
     }
 
     // Constructs the inverse directly instead of using `Self::Elem()`.
-    fn inv_(_: &Integer, x: &ClassElem) -> ClassElem {
-        ClassElem {
-            a: int(x.a.as_ref()).into(),
-            b: int(-(x.b.as_ref())).into(),
-            c: int(x.c.as_ref()).into(),
-        }
+    fn inv_(_: &Integer, x: &ClassElem<D>) -> ClassElem<D> {
+        ClassElem::new(int(x.a.as_ref()), int(-(x.b.as_ref())), int(x.c.as_ref()))
     }
 
-    fn exp_(_: &Integer, a: &ClassElem, n: &Integer) -> ClassElem {
-        let (mut val, mut a, mut n) = {
+    // Signed sliding windows (wNAF): inverses are free in class groups, so negative digits cost
+    // nothing and only the odd powers below `2^(w - 1)` need to be precomputed.
+    fn exp_(_: &Integer, a: &ClassElem<D>, n: &Integer) -> ClassElem<D> {
+        let (a, n) = {
             if *n < int(0) {
-                (Self::id(), Self::inv(a), int(-n))
+                (Self::inv(a), int(-n))
             } else {
-                (Self::id(), a.clone(), n.clone())
+                (a.clone(), n.clone())
             }
         };
-        loop {
-            if n == int(0) {
-                return val;
+        let width = wnaf_width(n.significant_bits());
+
+        // a, a^3, a^5, ..., a^(2^(w - 1) - 1)
+        let mut odd_powers = vec![a];
+        if width > 2 {
+            let a2 = Self::square(&odd_powers[0]);
+            for i in 1..1_usize << (width - 2) {
+                let next = Self::op(&odd_powers[i - 1], &a2);
+                odd_powers.push(next);
             }
-            if n.is_odd() {
-                val = Self::op(&val, &a);
+        }
+
+        let mut val: Option<ClassElem<D>> = None;
+        for digit in wnaf(&n, width).into_iter().rev() {
+            val = val.map(|v| Self::square(&v));
+            if digit != 0 {
+                let power = &odd_powers[(digit.abs() / 2) as usize];
+                let power = if digit > 0 {
+                    power.clone()
+                } else {
+                    Self::inv(power)
+                };
+                val = Some(match val {
+                    None => power,
+                    Some(v) => Self::op(&v, &power),
+                });
             }
-            a = Self::square(&a);
-            n >>= 1;
         }
+        val.unwrap_or_else(Self::id)
     }
-}
 
-impl ElemToBytes for ClassGroup {
-    fn elem_to_bytes(val: &ClassElem) -> Vec<u8> {
-        let mut all_bytes = vec![];
-        for e in &[val.a.clone(), val.b.clone(), val.c.clone()] {
-            let num = e;
-            let digits = num.as_ref().significant_digits::<u8>();
-            let mut bytes = vec![0u8; digits];
-            num.as_ref()
-                .write_digits(&mut bytes, rug::integer::Order::MsfBe);
-            all_bytes.extend_from_slice(&bytes);
+    // Montgomery ladder, like the default, but with the dedicated squaring.
+    fn exp_secret_(_: &Integer, a: &ClassElem<D>, n: &Integer) -> ClassElem<D> {
+        let (a, n) = {
+            if *n < int(0) {
+                (Self::inv(a), int(-n))
+            } else {
+                (a.clone(), n.clone())
+            }
+        };
+        let mut r0 = Self::id();
+        let mut r1 = a;
+        for i in (0..n.significant_bits()).rev() {
+            if n.get_bit(i) {
+                r0 = Self::op(&r0, &r1);
+                r1 = Self::square(&r1);
+            } else {
+                r1 = Self::op(&r0, &r1);
+                r0 = Self::square(&r0);
+            }
         }
-        all_bytes
+        r0
+    }
+
+    fn simul_exp_(_: &Integer, bases: &[ClassElem<D>], exps: &[Integer]) -> ClassElem<D> {
+        simul_exp::<Self, _>(bases, exps, Self::square)
     }
 }
 
-impl UnknownOrderGroup for ClassGroup {
-    fn unknown_order_elem_(d: &Integer) -> ClassElem {
+impl<D: DiscriminantSource> ElemToBytes for Class<D> {
+    fn elem_to_bytes(val: &ClassElem<D>) -> Vec<u8> {
+        val.to_bytes()
+    }
+}
+
+impl<D: DiscriminantSource> UnknownOrderGroup for Class<D> {
+    fn unknown_order_elem_(d: &Integer) -> ClassElem<D> {
         // a = 2
         // b = 1
         // c = (b * b - d) / 4a
         let a = int(2);
         let b = int(1);
         let c = int(1 - d) / int(8);
-        ClassElem {
-            a: a.into(),
-            b: b.into(),
-            c: c.into(),
-        }
+        ClassElem::new(a, b, c)
     }
 
-    fn unknown_possibly_random_order_elem_<R: MutRandState>(_: &Self::Rep, _: &mut R) -> ClassElem {
+    fn unknown_possibly_random_order_elem_<R: MutRandState>(
+        _: &Self::Rep,
+        _: &mut R,
+    ) -> ClassElem<D> {
         Self::unknown_order_elem()
     }
 
     // based on https://kconrad.math.uconn.edu/blurbs/gradnumthy/classgpex.pdf
-    fn order_upper_bound_(_: &Integer) -> Integer {
-        CLASS_GROUP_DISCRIMINANT.clone().abs().sqrt() + 1
+    fn order_upper_bound_(d: &Integer) -> Integer {
+        int(d.abs_ref()).sqrt() + 1
     }
 
     fn rsa_modulus_(_: &Integer) -> Result<Integer, Integer> {
         Err(Integer::from(0))
     }
+
+    fn validate_(_: &Integer, x: &ClassElem<D>) -> Result<(), ElemError> {
+        let (a, b, c) = (x.a.as_ref(), x.b.as_ref(), x.c.as_ref());
+        if !Self::has_discriminant(a, b, c) {
+            return Err(ElemError::WrongDiscriminant);
+        }
+        if *a <= 0 || !Self::is_reduced(a, b, c) {
+            return Err(ElemError::NotReduced);
+        }
+        Ok(())
+    }
 }
 
-impl Hash for ClassElem {
+impl<D: DiscriminantSource> Hash for ClassElem<D> {
     // Assumes `ClassElem` is reduced and normalized, which will be the case unless a struct is
     // instantiated manually in this module.
     fn hash<H: Hasher>(&self, state: &mut H) {
@@ -280,34 +641,78 @@ impl Hash for ClassElem {
     }
 }
 
-impl PartialEq for ClassElem {
+impl<D: DiscriminantSource> PartialEq for ClassElem<D> {
     fn eq(&self, other: &Self) -> bool {
         self.a == other.a && self.b == other.b && self.c == other.c
     }
 }
 
 /// Panics if `(a, b, c)` cannot be reduced to a valid class element.
-impl<A, B, C> ElemFrom<(A, B, C)> for ClassGroup
+impl<D: DiscriminantSource, A, B, C> ElemFrom<(A, B, C)> for Class<D>
 where
     Integer: From<A>,
     Integer: From<B>,
     Integer: From<C>,
 {
-    fn elem(abc: (A, B, C)) -> ClassElem {
+    fn elem(abc: (A, B, C)) -> ClassElem<D> {
         let (a, b, c) = Self::reduce(int(abc.0), int(abc.1), int(abc.2));
 
         // Ideally, this should return an error and the return type of `ElemFrom` should be
         // `Result<Self::Elem, Self:err>`, but this would require a lot of ugly `unwrap`s in the
         // accumulator library. Besides, users should not need to create new class group elements, so
         // an invalid `ElemFrom` here should signal a severe internal error.
-        assert!(Self::validate(&a, &b, &c));
+        assert!(Self::has_discriminant(&a, &b, &c));
 
-        ClassElem {
-            a: a.into(),
-            b: b.into(),
-            c: c.into(),
+        ClassElem::new(a, b, c)
+    }
+}
+
+// `n mod m` for positive `m`.
+fn rem_euc(n: Integer, m: &Integer) -> Integer {
+    <(Integer, Integer)>::from(n.div_rem_euc_ref(m)).1
+}
+
+// Extended Euclidean algorithm on `(r2, r1)`, stopped as soon as `r1 <= bound`. Returns the
+// cofactors of the last two remainders with respect to the initial `r1`, and the last remainder.
+fn partial_xgcd(mut r2: Integer, mut r1: Integer, bound: &Integer) -> (Integer, Integer, Integer) {
+    let mut co2 = int(0);
+    let mut co1 = int(-1);
+    while r1 > *bound {
+        let (q, r) = r2.div_rem_floor(r1.clone());
+        r2 = r1;
+        r1 = r;
+        co2 -= q * &co1;
+        mem::swap(&mut co2, &mut co1);
+    }
+    (co2, co1, r1)
+}
+
+// Window width `w` minimizing the cost of a `bits`-bit wNAF exponentiation: `2^(w - 2)`
+// multiplications to precompute the odd powers, plus about `bits / (w + 1)` in the main loop.
+fn wnaf_width(bits: u32) -> u32 {
+    (2..=MAX_WNAF_WIDTH)
+        .min_by_key(|w| (1_u32 << (w - 2)) + bits / (w + 1))
+        .unwrap()
+}
+
+// Width-`w` non-adjacent form of the non-negative `n`, least significant digit first. The non-zero
+// digits are odd and below `2^(w - 1)` in absolute value.
+fn wnaf(n: &Integer, width: u32) -> Vec<i32> {
+    let mut n = n.clone();
+    let mut digits = Vec::with_capacity(n.significant_bits() as usize + 1);
+    while n > 0 {
+        let mut digit = 0;
+        if n.is_odd() {
+            digit = n.mod_u(1 << width) as i32;
+            if digit >= 1 << (width - 1) {
+                digit -= 1 << width;
+            }
+            n -= digit;
         }
+        digits.push(digit);
+        n >>= 1;
     }
+    digits
 }
 
 // Caveat: Tests that use "ground truth" use outputs from Chia's sample implementation in python:
@@ -319,11 +724,11 @@ mod tests {
 
     // Makes a class elem tuple but does not reduce.
     fn construct_raw_elem_from_strings(a: &str, b: &str, c: &str) -> ClassElem {
-        ClassElem {
-            a: Integer::from_str(a).unwrap().into(),
-            b: Integer::from_str(b).unwrap().into(),
-            c: Integer::from_str(c).unwrap().into(),
-        }
+        ClassElem::new(
+            Integer::from_str(a).unwrap(),
+            Integer::from_str(b).unwrap(),
+            Integer::from_str(c).unwrap(),
+        )
     }
 
     #[should_panic]
@@ -513,14 +918,7 @@ mod tests {
         let (a, b, c) =
             ClassGroup::reduce(to_reduce.a.into(), to_reduce.b.into(), to_reduce.c.into());
 
-        assert_eq!(
-            ClassElem {
-                a: a.into(),
-                b: b.into(),
-                c: c.into()
-            },
-            reduced_ground_truth
-        );
+        assert_eq!(ClassElem::new(a, b, c), reduced_ground_truth);
 
         let reduced_ground_truth_ = reduced_ground_truth.clone();
         let (a, b, c) = ClassGroup::reduce(
@@ -528,14 +926,7 @@ mod tests {
             reduced_ground_truth_.b.into(),
             reduced_ground_truth_.c.into(),
         );
-        assert_eq!(
-            ClassElem {
-                a: a.into(),
-                b: b.into(),
-                c: c.into()
-            },
-            reduced_ground_truth
-        );
+        assert_eq!(ClassElem::new(a, b, c), reduced_ground_truth);
     }
 
     #[test]
@@ -571,14 +962,7 @@ mod tests {
             unnormalized.b.into(),
             unnormalized.c.into(),
         );
-        assert_eq!(
-            normalized_ground_truth,
-            ClassElem {
-                a: a.into(),
-                b: b.into(),
-                c: c.into()
-            }
-        );
+        assert_eq!(normalized_ground_truth, ClassElem::new(a, b, c));
     }
 
     #[test]
@@ -601,27 +985,27 @@ mod tests {
         let g3 = ClassGroup::op(&id, &g2);
         let g3_inv = ClassGroup::inv(&g3);
 
-        assert!(ClassGroup::validate(
+        assert!(ClassGroup::has_discriminant(
             id.a.as_ref(),
             id.b.as_ref(),
             id.c.as_ref()
         ));
-        assert!(ClassGroup::validate(
+        assert!(ClassGroup::has_discriminant(
             g1.a.as_ref(),
             g1.b.as_ref(),
             g1.c.as_ref()
         ));
-        assert!(ClassGroup::validate(
+        assert!(ClassGroup::has_discriminant(
             g2.a.as_ref(),
             g2.b.as_ref(),
             g2.c.as_ref()
         ));
-        assert!(ClassGroup::validate(
+        assert!(ClassGroup::has_discriminant(
             g3.a.as_ref(),
             g3.b.as_ref(),
             g3.c.as_ref()
         ));
-        assert!(ClassGroup::validate(
+        assert!(ClassGroup::has_discriminant(
             g3_inv.a.as_ref(),
             g3_inv.b.as_ref(),
             g3_inv.c.as_ref()
@@ -720,7 +1104,7 @@ mod tests {
         let mut g_star = ClassGroup::id();
         for i in 1..=1000 {
             g = ClassGroup::op(&g_anchor, &g);
-            assert!(ClassGroup::validate(
+            assert!(ClassGroup::has_discriminant(
                 g.a.as_ref(),
                 g.b.as_ref(),
                 g.c.as_ref()
@@ -729,7 +1113,7 @@ mod tests {
                 gs.push(g.clone());
                 gs_invs.push(ClassGroup::inv(&g));
                 g_star = ClassGroup::op(&g, &g_star);
-                assert!(ClassGroup::validate(
+                assert!(ClassGroup::has_discriminant(
                     g_star.a.as_ref(),
                     g_star.b.as_ref(),
                     g_star.c.as_ref()
@@ -739,12 +1123,12 @@ mod tests {
 
         let elems_n_invs = gs.iter().zip(gs_invs.iter());
         for (g_elem, g_inv) in elems_n_invs {
-            assert!(ClassGroup::validate(
+            assert!(ClassGroup::has_discriminant(
                 g_elem.a.as_ref(),
                 g_elem.b.as_ref(),
                 g_elem.c.as_ref()
             ));
-            assert!(ClassGroup::validate(
+            assert!(ClassGroup::has_discriminant(
                 g_inv.a.as_ref(),
                 g_inv.b.as_ref(),
                 g_inv.c.as_ref()
@@ -753,7 +1137,7 @@ mod tests {
             for elem in &gs {
                 if elem != g_elem {
                     curr_prod = ClassGroup::op(&curr_prod, &elem);
-                    assert!(ClassGroup::validate(
+                    assert!(ClassGroup::has_discriminant(
                         curr_prod.a.as_ref(),
                         curr_prod.b.as_ref(),
                         curr_prod.c.as_ref()
@@ -831,4 +1215,185 @@ mod tests {
 
         assert_eq!(&g2, &g4);
     }
+
+    // `g, g^2, ..., g^n` for the `unknown_order_elem` `g`, along with their inverses.
+    fn powers<D: DiscriminantSource>(n: usize) -> Vec<ClassElem<D>> {
+        let g = Class::<D>::unknown_order_elem();
+        let mut elems = vec![Class::<D>::id(), g.clone()];
+        for _ in 1..n {
+            let next = Class::<D>::compose(elems.last().unwrap(), &g);
+            elems.push(next);
+        }
+        let inverses: Vec<_> = elems.iter().map(Class::<D>::inv).collect();
+        elems.extend(inverses);
+        elems
+    }
+
+    fn check_nucomp<D: DiscriminantSource>() {
+        let elems = powers::<D>(20);
+        for x in &elems {
+            assert_eq!(Class::<D>::square(x), Class::<D>::compose(x, x));
+            for y in &elems {
+                assert_eq!(Class::<D>::op(x, y), Class::<D>::compose(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_nucomp() {
+        check_nucomp::<Discriminant2048>();
+        check_nucomp::<TestSeed>();
+    }
+
+    #[test]
+    fn test_wnaf() {
+        let n = Integer::from_str("123456789012345678901234567890123456789").unwrap();
+        for width in 2..=MAX_WNAF_WIDTH {
+            let digits = wnaf(&n, width);
+            let mut recoded = int(0);
+            for (i, &digit) in digits.iter().enumerate() {
+                assert!(digit == 0 || (digit % 2 != 0 && digit.abs() < 1 << (width - 1)));
+                if digit != 0 {
+                    assert!(digits[i + 1..]
+                        .iter()
+                        .take(width as usize - 1)
+                        .all(|&d| d == 0));
+                }
+                recoded += int(digit) << i as u32;
+            }
+            assert_eq!(recoded, n);
+        }
+        assert_eq!(wnaf_width(16), 2);
+        assert_eq!(wnaf_width(2048), MAX_WNAF_WIDTH);
+    }
+
+    #[test]
+    fn test_exp_large() {
+        let g = ClassGroup::unknown_order_elem();
+        let a = Integer::from_str("340282366920938463463374607431768211507").unwrap();
+        let b = Integer::from_str("-18446744073709551557").unwrap();
+        let ga = ClassGroup::exp(&g, &a);
+        let gb = ClassGroup::exp(&g, &b);
+        assert_eq!(ClassGroup::op(&ga, &gb), ClassGroup::exp(&g, &int(&a + &b)));
+        assert_eq!(ClassGroup::exp(&ga, &b), ClassGroup::exp(&g, &int(&a * &b)));
+        assert_eq!(ClassGroup::inv(&gb), ClassGroup::exp(&g, &int(-&b)));
+        assert_eq!(ClassGroup::exp(&g, &int(0)), ClassGroup::id());
+    }
+
+    #[test]
+    fn test_compressed_encoding() {
+        // 1 + 2 * 128 bytes for the 2048-bit discriminant.
+        for x in powers::<Discriminant2048>(20) {
+            let bytes = ClassGroup::elem_to_bytes(&x);
+            assert_eq!(bytes.len(), 257);
+            assert_eq!(ClassElem::from_bytes(&bytes), Some(x));
+        }
+        for x in powers::<TestSeed>(20) {
+            assert_eq!(ClassElem::from_bytes(&x.to_bytes()), Some(x));
+        }
+
+        let bytes = ClassGroup::unknown_order_elem().to_bytes();
+        assert_eq!(ClassElem::<Discriminant2048>::from_bytes(&bytes[1..]), None);
+        // `(2, -1, c)` is the inverse, but `(1, -1, c)` is not reduced.
+        let mut negated = bytes.clone();
+        negated[0] = 1;
+        assert_eq!(
+            ClassElem::from_bytes(&negated),
+            Some(ClassGroup::inv(&ClassGroup::unknown_order_elem()))
+        );
+        let mut negated = ClassGroup::id().to_bytes();
+        negated[0] = 1;
+        assert_eq!(ClassElem::<Discriminant2048>::from_bytes(&negated), None);
+        negated[0] = 2;
+        assert_eq!(ClassElem::<Discriminant2048>::from_bytes(&negated), None);
+        // `(3, 1, c)` has no integral `c`.
+        let mut wrong_a = bytes;
+        wrong_a[128] = 3;
+        assert_eq!(ClassElem::<Discriminant2048>::from_bytes(&wrong_a), None);
+        // Elements of another group are rejected.
+        let h = Class::<TestSeed>::unknown_order_elem();
+        assert_eq!(
+            ClassElem::<Discriminant2048>::from_bytes(&h.to_bytes()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let g = ClassGroup::unknown_order_elem();
+        assert_eq!(ClassGroup::validate(&g), Ok(()));
+
+        // `(c, 1, 2)` has the right discriminant, but is not reduced.
+        let swapped = ClassElem::new(g.c.as_ref().clone(), int(1), int(2));
+        assert_eq!(ClassGroup::validate(&swapped), Err(ElemError::NotReduced));
+        let negative = ClassElem::new(int(-2), int(1), int(-g.c.as_ref()));
+        assert_eq!(ClassGroup::validate(&negative), Err(ElemError::NotReduced));
+
+        let shifted = ClassElem::new(int(2), int(1), int(g.c.as_ref() + 1));
+        assert_eq!(
+            ClassGroup::validate(&shifted),
+            Err(ElemError::WrongDiscriminant)
+        );
+        let h = Class::<TestSeed>::unknown_order_elem();
+        let foreign = ClassElem::new(
+            h.a.as_ref().clone(),
+            h.b.as_ref().clone(),
+            h.c.as_ref().clone(),
+        );
+        assert_eq!(
+            ClassGroup::validate(&foreign),
+            Err(ElemError::WrongDiscriminant)
+        );
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    enum TestSeed {}
+
+    impl SeededDiscriminant for TestSeed {
+        const SEED: &'static [u8] = b"test class group";
+        const BITS: u32 = 512;
+
+        fn cell() -> &'static DiscriminantCell {
+            static CELL: DiscriminantCell = DiscriminantCell::new();
+            &CELL
+        }
+    }
+
+    #[test]
+    fn test_create_discriminant() {
+        let d = create_discriminant(b"seed", 256);
+        assert_eq!(d, create_discriminant(b"seed", 256));
+        assert_ne!(d, create_discriminant(b"other seed", 256));
+        assert_eq!(d.significant_bits(), 256);
+        assert_eq!(d.mod_u(8), 1);
+        assert_ne!(int(-&d).is_probably_prime(PRIMALITY_REPS), IsPrime::No);
+    }
+
+    #[test]
+    fn test_seeded_group() {
+        type Seeded = Class<TestSeed>;
+        assert_eq!(
+            *Seeded::rep(),
+            create_discriminant(TestSeed::SEED, TestSeed::BITS)
+        );
+        // computed once, then served from the cell of the type
+        assert!(std::ptr::eq(Seeded::rep(), Seeded::rep()));
+        assert!(Seeded::order_upper_bound() < ClassGroup::order_upper_bound());
+
+        let id = Seeded::id();
+        let g = Seeded::unknown_order_elem();
+        assert!(Seeded::has_discriminant(
+            g.a.as_ref(),
+            g.b.as_ref(),
+            g.c.as_ref()
+        ));
+        assert!(!ClassGroup::has_discriminant(
+            g.a.as_ref(),
+            g.b.as_ref(),
+            g.c.as_ref()
+        ));
+        let g5 = Seeded::exp(&g, &int(5));
+        assert_eq!(Seeded::op(&g5, &Seeded::exp(&g, &int(-5))), id);
+        assert_eq!(Seeded::op(&g5, &Seeded::inv(&g5)), id);
+    }
 }
@@ -11,12 +11,22 @@ use std::hash::Hash;
 use std::marker::Sized;
 
 mod class;
-pub use class::{ClassElem, ClassGroup};
+pub use class::{
+    create_discriminant, Class, ClassElem, ClassGroup, Discriminant2048, DiscriminantCell,
+    DiscriminantSource, SeededDiscriminant,
+};
+mod multiexp;
+pub use multiexp::simul_exp;
 //mod ristretto;
 //pub use ristretto::{Ristretto, RistrettoElem};
 mod rsa;
 pub use rsa::{Rsa2048, Rsa2048Elem};
 pub use rsa::{Rsa3072, Rsa3072Elem};
+pub use rsa::{Rsa4096, Rsa4096Elem, Rsa4096Modulus};
+pub use rsa::{
+    ModulusSource, Rsa, RsaElem, RsaModulus, RsaModulusError, RsaRuntime, RuntimeModulus,
+    MIN_MODULUS_BITS,
+};
 
 use serde::Serialize;
 
@@ -75,9 +85,46 @@ pub trait Group: Clone + Debug + Eq + Hash + TypeRep + Send + Sync + Serialize {
         val
     }
 
+    /// A group-specific wrapper for `exp_secret`, although it comes with a default implementation
+    /// via the Montgomery ladder, which performs the same sequence of group operations for every
+    /// exponent of a given bit length.
+    ///
+    /// The ladder is only as side-channel resistant as `op` itself; specific implementations may
+    /// provide a constant-time specialization (e.g. GMP's `mpz_powm_sec` for RSA groups).
+    fn exp_secret_(_rep: &Self::Rep, a: &Self::Elem, n: &Integer) -> Self::Elem {
+        let (a, n) = {
+            if *n < int(0) {
+                (Self::inv(a), int(-n))
+            } else {
+                (a.clone(), n.clone())
+            }
+        };
+        // Invariant: r1 = r0 * a
+        let mut r0 = Self::id();
+        let mut r1 = a;
+        for i in (0..n.significant_bits()).rev() {
+            if n.get_bit(i) {
+                r0 = Self::op(&r0, &r1);
+                r1 = Self::op(&r1, &r1);
+            } else {
+                r1 = Self::op(&r0, &r1);
+                r0 = Self::op(&r0, &r0);
+            }
+        }
+        r0
+    }
+
     /// A group-specific wrapper for `inv`.
     fn inv_(rep: &Self::Rep, a: &Self::Elem) -> Self::Elem;
 
+    /// A group-specific wrapper for `simul_exp`, although it comes with a default implementation
+    /// via Straus' / Pippenger's method (see `multiexp`).
+    ///
+    /// Specific implementations may provide a faster squaring (e.g. class groups).
+    fn simul_exp_(_rep: &Self::Rep, bases: &[Self::Elem], exps: &[Integer]) -> Self::Elem {
+        simul_exp::<Self, _>(bases, exps, |a| Self::op(a, a))
+    }
+
     // -------------------
     // END OF REQUIRED FNS
     // -------------------
@@ -97,10 +144,39 @@ pub trait Group: Clone + Debug + Eq + Hash + TypeRep + Send + Sync + Serialize {
         Self::exp_(Self::rep(), a, n)
     }
 
+    /// Like `exp`, but for secret exponents (keys, witnesses, blinding factors): the running time
+    /// depends only on the sign and bit length of `n`, as far as the group allows. Slower than
+    /// `exp`, which should be preferred for public exponents.
+    fn exp_secret(a: &Self::Elem, n: &Integer) -> Self::Elem {
+        Self::exp_secret_(Self::rep(), a, n)
+    }
+
     /// Returns the group inverse of `a`.
     fn inv(a: &Self::Elem) -> Self::Elem {
         Self::inv_(Self::rep(), a)
     }
+
+    /// Returns the product of `bases[i] ^ exps[i]`, computed simultaneously.
+    fn simul_exp(bases: &[Self::Elem], exps: &[Integer]) -> Self::Elem {
+        Self::simul_exp_(Self::rep(), bases, exps)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+/// The different reasons an element can fail `UnknownOrderGroup::validate`.
+pub enum ElemError {
+    /// The element is not the canonical representative of its class (e.g. zero or larger than
+    /// half the modulus in an RSA group).
+    NotCanonical,
+
+    /// The element shares a factor with the RSA modulus.
+    NotInvertible,
+
+    /// The form has a different discriminant than the class group.
+    WrongDiscriminant,
+
+    /// The form is not reduced.
+    NotReduced,
 }
 
 /// A group containing elements of unknown order.
@@ -128,6 +204,12 @@ pub trait UnknownOrderGroup: Group {
         Self::rsa_modulus_(Self::rep())
     }
 
+    /// Checks that `a` is a canonical element of the group. Elements produced by the group
+    /// operations always are; anything received from another party should be checked before use.
+    fn validate(a: &Self::Elem) -> Result<(), ElemError> {
+        Self::validate_(Self::rep(), a)
+    }
+
     /// A group-specific wrapper for `unknown_order_elem`.
     fn unknown_order_elem_(rep: &Self::Rep) -> Self::Elem;
 
@@ -142,6 +224,9 @@ pub trait UnknownOrderGroup: Group {
 
     /// A group-specific wrapper for `rsa_modulus`.
     fn rsa_modulus_(rep: &Self::Rep) -> Result<Integer, Integer>;
+
+    /// A group-specific wrapper for `validate`.
+    fn validate_(rep: &Self::Rep, a: &Self::Elem) -> Result<(), ElemError>;
 }
 
 /// Like `From<T>`, but implemented on the `Group` instead of the element type.
@@ -196,4 +281,44 @@ mod tests {
         let res_2 = multi_exp::<Rsa2048>(&[alpha_1, alpha_2, alpha_3], &[x_1, x_2, x_3]);
         assert!(res_2 == Rsa2048::elem(1_687_500));
     }
+
+    fn test_exp_secret<G: UnknownOrderGroup>() {
+        let g = G::unknown_order_elem();
+        let n = G::order_upper_bound() - 1;
+        for n in &[int(0), int(1), int(2), int(-7), n.clone(), int(-&n)] {
+            assert_eq!(G::exp_secret(&g, n), G::exp(&g, n));
+        }
+    }
+
+    #[test]
+    fn test_exp_secret_rsa() {
+        test_exp_secret::<Rsa2048>();
+        test_exp_secret::<Rsa3072>();
+        test_exp_secret::<Rsa4096>();
+    }
+
+    #[test]
+    fn test_exp_secret_class() {
+        test_exp_secret::<ClassGroup>();
+    }
+
+    fn test_validate<G: UnknownOrderGroup>() {
+        let g = G::unknown_order_elem();
+        let x = G::exp(&g, &(G::order_upper_bound() - 1));
+        for a in &[G::id(), g.clone(), G::inv(&g), x.clone(), G::op(&g, &x)] {
+            assert_eq!(G::validate(a), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_validate_rsa() {
+        test_validate::<Rsa2048>();
+        test_validate::<Rsa3072>();
+        test_validate::<Rsa4096>();
+    }
+
+    #[test]
+    fn test_validate_class() {
+        test_validate::<ClassGroup>();
+    }
 }
@@ -0,0 +1,215 @@
+//! Simultaneous multi-exponentiation, i.e. computing `bases[0] ^ exps[0] * ... * bases[n] ^ exps[n]`
+//! with (far) fewer squarings than `n` separate exponentiations. Uses Straus' interleaved windows
+//! for few bases and Pippenger's bucket method for many bases.
+use super::Group;
+use crate::util::int;
+use rug::Integer;
+
+/// Number of bases from which Pippenger's method is used instead of Straus'.
+const PIPPENGER_THRESHOLD: usize = 32;
+
+// Multiplies the accumulator (`None` is the identity) with `x`.
+fn mul<G: Group>(acc: Option<G::Elem>, x: &G::Elem) -> Option<G::Elem> {
+    Some(match acc {
+        None => x.clone(),
+        Some(acc) => G::op(&acc, x),
+    })
+}
+
+// Squares the accumulator `width` times.
+fn square_n<G: Group, S: Fn(&G::Elem) -> G::Elem>(
+    acc: Option<G::Elem>,
+    width: u32,
+    square: &S,
+) -> Option<G::Elem> {
+    acc.map(|mut acc| {
+        for _ in 0..width {
+            acc = square(&acc);
+        }
+        acc
+    })
+}
+
+// Bits `pos..pos + width` of the (non-negative) integer `n`.
+fn digit(n: &Integer, pos: u32, width: u32) -> usize {
+    (0..width)
+        .rev()
+        .fold(0, |d, i| (d << 1) | n.get_bit(pos + i) as usize)
+}
+
+/// Computes the product of `bases[i] ^ exps[i]`, using `square` to square group elements.
+///
+/// Groups with a dedicated squaring routine (e.g. class groups) should pass it here, otherwise
+/// use `|a| G::op(a, a)`.
+pub fn simul_exp<G: Group, S: Fn(&G::Elem) -> G::Elem>(
+    bases: &[G::Elem],
+    exps: &[Integer],
+    square: S,
+) -> G::Elem {
+    assert_eq!(bases.len(), exps.len());
+
+    // make all exponents non-negative
+    let (bases, exps): (Vec<G::Elem>, Vec<Integer>) = bases
+        .iter()
+        .zip(exps.iter())
+        .map(|(base, exp)| {
+            if *exp < int(0) {
+                (G::inv(base), int(-exp))
+            } else {
+                (base.clone(), exp.clone())
+            }
+        })
+        .unzip();
+
+    let bits = exps
+        .iter()
+        .map(Integer::significant_bits)
+        .max()
+        .unwrap_or(0);
+    let res = if bases.len() < PIPPENGER_THRESHOLD {
+        straus::<G, S>(&bases, &exps, bits, &square)
+    } else {
+        pippenger::<G, S>(&bases, &exps, bits, &square)
+    };
+    res.unwrap_or_else(G::id)
+}
+
+fn straus<G: Group, S: Fn(&G::Elem) -> G::Elem>(
+    bases: &[G::Elem],
+    exps: &[Integer],
+    bits: u32,
+    square: &S,
+) -> Option<G::Elem> {
+    let width = match bits {
+        0..=64 => 2,
+        65..=256 => 3,
+        257..=1024 => 4,
+        _ => 5,
+    };
+
+    // tables[i][d - 1] = bases[i] ^ d
+    let tables: Vec<Vec<G::Elem>> = bases
+        .iter()
+        .map(|base| {
+            let mut table = vec![base.clone()];
+            for _ in 2..(1 << width) {
+                let next = G::op(&table[table.len() - 1], base);
+                table.push(next);
+            }
+            table
+        })
+        .collect();
+
+    let mut acc = None;
+    for window in (0..(bits + width - 1) / width).rev() {
+        acc = square_n::<G, S>(acc, width, square);
+        for (table, exp) in tables.iter().zip(exps.iter()) {
+            let d = digit(exp, window * width, width);
+            if d != 0 {
+                acc = mul::<G>(acc, &table[d - 1]);
+            }
+        }
+    }
+    acc
+}
+
+fn pippenger<G: Group, S: Fn(&G::Elem) -> G::Elem>(
+    bases: &[G::Elem],
+    exps: &[Integer],
+    bits: u32,
+    square: &S,
+) -> Option<G::Elem> {
+    // window of roughly log2(#bases) bits
+    let mut width = 1;
+    while (1 << (width + 1)) <= bases.len() {
+        width += 1;
+    }
+
+    let mut acc = None;
+    for window in (0..(bits + width - 1) / width).rev() {
+        acc = square_n::<G, S>(acc, width, square);
+
+        // sort bases into buckets by digit
+        let mut buckets: Vec<Option<G::Elem>> = vec![None; (1 << width) - 1];
+        for (base, exp) in bases.iter().zip(exps.iter()) {
+            let d = digit(exp, window * width, width);
+            if d != 0 {
+                buckets[d - 1] = mul::<G>(buckets[d - 1].take(), base);
+            }
+        }
+
+        // prod_d bucket[d] ^ d via running products
+        let mut running = None;
+        let mut total = None;
+        for bucket in buckets.iter().rev() {
+            if let Some(bucket) = bucket {
+                running = mul::<G>(running, bucket);
+            }
+            if let Some(running) = &running {
+                total = mul::<G>(total, running);
+            }
+        }
+        if let Some(total) = total {
+            acc = mul::<G>(acc, &total);
+        }
+    }
+    acc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::{ClassGroup, ElemFrom, Rsa2048, Rsa3072, UnknownOrderGroup};
+    use rug::rand::RandState;
+
+    fn test_simul_exp<G: UnknownOrderGroup>(n: usize) {
+        let mut rng = RandState::new();
+        let g = G::unknown_possibly_random_order_elem(&mut rng);
+        let bases: Vec<G::Elem> = (0..n).map(|i| G::exp(&g, &int(i + 2))).collect();
+        let exps: Vec<Integer> = (0..n)
+            .map(|i| {
+                let exp = int(int(1) << 300).random_below(&mut rng);
+                if i % 3 == 0 {
+                    -exp
+                } else {
+                    exp
+                }
+            })
+            .collect();
+
+        let expected = bases
+            .iter()
+            .zip(exps.iter())
+            .fold(G::id(), |acc, (base, exp)| G::op(&acc, &G::exp(base, exp)));
+        assert_eq!(G::simul_exp(&bases, &exps), expected);
+    }
+
+    #[test]
+    fn test_simul_exp_small() {
+        let res = Rsa2048::simul_exp(&[Rsa2048::elem(2), Rsa2048::elem(3)], &[int(3), int(2)]);
+        assert_eq!(res, Rsa2048::elem(72));
+        assert_eq!(Rsa2048::simul_exp(&[], &[]), Rsa2048::id());
+        assert_eq!(
+            Rsa2048::simul_exp(&[Rsa2048::elem(5)], &[int(0)]),
+            Rsa2048::id()
+        );
+    }
+
+    #[test]
+    fn test_simul_exp_rsa2048() {
+        test_simul_exp::<Rsa2048>(4);
+        test_simul_exp::<Rsa2048>(PIPPENGER_THRESHOLD + 5);
+    }
+
+    #[test]
+    fn test_simul_exp_rsa3072() {
+        test_simul_exp::<Rsa3072>(4);
+        test_simul_exp::<Rsa3072>(PIPPENGER_THRESHOLD + 5);
+    }
+
+    #[test]
+    fn test_simul_exp_class() {
+        test_simul_exp::<ClassGroup>(4);
+        test_simul_exp::<ClassGroup>(PIPPENGER_THRESHOLD + 5);
+    }
+}
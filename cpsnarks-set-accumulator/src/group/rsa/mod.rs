@@ -1,8 +1,30 @@
 mod rsa2048;
 mod rsa3072;
+mod rsa4096;
+mod runtime;
 
-use super::{ElemFrom, ElemToBytes, Group, UnknownOrderGroup};
+use super::{ElemError, ElemFrom, ElemToBytes, Group, UnknownOrderGroup};
+use rug::Integer;
 
 pub use rsa2048::{Rsa2048, Rsa2048Elem};
 
 pub use rsa3072::{Rsa3072, Rsa3072Elem};
+
+pub use rsa4096::{Rsa4096, Rsa4096Elem, Rsa4096Modulus};
+
+pub use runtime::{
+    ModulusSource, Rsa, RsaElem, RsaModulus, RsaModulusError, RsaRuntime, RuntimeModulus,
+    MIN_MODULUS_BITS,
+};
+
+// Elements are represented by the smaller of `x` and `-x`, so valid ones lie in `[1, N / 2]` and
+// are invertible.
+fn validate_elem(x: &Integer, modulus: &Integer, half_modulus: &Integer) -> Result<(), ElemError> {
+    if *x <= 0 || x > half_modulus {
+        return Err(ElemError::NotCanonical);
+    }
+    if Integer::from(x.gcd_ref(modulus)) != 1 {
+        return Err(ElemError::NotInvertible);
+    }
+    Ok(())
+}
@@ -1,5 +1,5 @@
 //! RSA (2048) group using GMP integers in the `rug` crate.
-use super::{ElemFrom, ElemToBytes, Group, UnknownOrderGroup};
+use super::{validate_elem, ElemError, ElemFrom, ElemToBytes, Group, UnknownOrderGroup};
 use crate::util::{int, TypeRep};
 use rug::{rand::MutRandState, Integer};
 use rug_binserial::Integer as BinInteger;
@@ -56,9 +56,20 @@ impl Group for Rsa2048 {
     }
 
     fn exp_(modulus: &Integer, x: &Rsa2048Elem, n: &Integer) -> Rsa2048Elem {
-        // A side-channel resistant impl is 40% slower; we'll consider it in the future if we need to.
+        // A side-channel resistant impl is 40% slower; secret exponents go through `exp_secret`.
         Self::elem(x.0.as_ref().pow_mod_ref(n, modulus).unwrap())
     }
+
+    fn exp_secret_(modulus: &Integer, x: &Rsa2048Elem, n: &Integer) -> Rsa2048Elem {
+        // GMP's `mpz_powm_sec` requires a positive exponent.
+        if *n == 0 {
+            Self::id()
+        } else if *n < 0 {
+            Self::exp_secret_(modulus, &Self::inv(x), &int(-n))
+        } else {
+            Self::elem(x.0.as_ref().secure_pow_mod_ref(n, modulus))
+        }
+    }
 }
 
 impl<T> ElemFrom<T> for Rsa2048
@@ -110,6 +121,10 @@ impl UnknownOrderGroup for Rsa2048 {
     fn rsa_modulus_(_: &Integer) -> Result<Integer, Integer> {
         Ok(RSA2048_MODULUS.clone())
     }
+
+    fn validate_(modulus: &Integer, x: &Rsa2048Elem) -> Result<(), ElemError> {
+        validate_elem(x.0.as_ref(), modulus, &HALF_MODULUS)
+    }
 }
 
 #[cfg(test)]
@@ -121,6 +136,24 @@ mod tests {
         let _x = &Rsa2048::rep();
     }
 
+    #[test]
+    fn test_validate() {
+        assert_eq!(Rsa2048::validate(&Rsa2048::elem(2)), Ok(()));
+        assert_eq!(Rsa2048::validate(&Rsa2048::elem(-2)), Ok(()));
+        assert_eq!(
+            Rsa2048::validate(&Rsa2048Elem(int(0).into())),
+            Err(ElemError::NotCanonical)
+        );
+        assert_eq!(
+            Rsa2048::validate(&Rsa2048Elem((RSA2048_MODULUS.clone() - 2).into())),
+            Err(ElemError::NotCanonical)
+        );
+        assert_eq!(
+            validate_elem(&int(6), &int(35), &int(17)),
+            Err(ElemError::NotInvertible)
+        );
+    }
+
     #[test]
     fn test_op() {
         let a = Rsa2048::op(&Rsa2048::elem(2), &Rsa2048::elem(3));
@@ -1,5 +1,5 @@
 //! RSA (3072Rsa3072) group using GMP integers in the `rug` crate.
-use super::{ElemFrom, ElemToBytes, Group, UnknownOrderGroup};
+use super::{validate_elem, ElemError, ElemFrom, ElemToBytes, Group, UnknownOrderGroup};
 use crate::util::{int, TypeRep};
 use rug::{rand::MutRandState, Integer};
 use rug_binserial::Integer as BinInteger;
@@ -45,9 +45,20 @@ impl Group for Rsa3072 {
   }
 
   fn exp_(modulus: &Integer, x: &Rsa3072Elem, n: &Integer) -> Rsa3072Elem {
-    // A side-channel resistant impl is 40% slower; we'll consider it in the future if we need to.
+    // A side-channel resistant impl is 40% slower; secret exponents go through `exp_secret`.
     Self::elem(x.0.as_ref().pow_mod_ref(n, modulus).unwrap())
   }
+
+  fn exp_secret_(modulus: &Integer, x: &Rsa3072Elem, n: &Integer) -> Rsa3072Elem {
+    // GMP's `mpz_powm_sec` requires a positive exponent.
+    if *n == 0 {
+      Self::id()
+    } else if *n < 0 {
+      Self::exp_secret_(modulus, &Self::inv(x), &int(-n))
+    } else {
+      Self::elem(x.0.as_ref().secure_pow_mod_ref(n, modulus))
+    }
+  }
 }
 
 impl<T> ElemFrom<T> for Rsa3072
@@ -100,6 +111,10 @@ impl UnknownOrderGroup for Rsa3072 {
   fn rsa_modulus_(_: &Integer) -> Result<Integer, Integer> {
     Ok(RSA3072_MODULUS.clone())
   }
+
+  fn validate_(modulus: &Integer, x: &Rsa3072Elem) -> Result<(), ElemError> {
+    validate_elem(x.0.as_ref(), modulus, &HALF_MODULUS)
+  }
 }
 
 #[cfg(test)]
@@ -0,0 +1,68 @@
+//! RSA (4096) group, with the modulus loaded from the bundled `rsa4096.txt` parameter file.
+use super::runtime::{ModulusSource, Rsa, RsaElem, RsaModulus};
+use serde::{Deserialize, Serialize};
+
+/// Contents of the RSA-4096 parameter file. Generated by a single party, see the file header.
+const RSA4096_PARAMETERS: &str = include_str!("rsa4096.txt");
+
+lazy_static! {
+    pub static ref RSA4096_MODULUS: RsaModulus = RSA4096_PARAMETERS.parse().unwrap();
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Modulus source for `Rsa4096`.
+pub enum Rsa4096Modulus {}
+
+impl ModulusSource for Rsa4096Modulus {
+    fn modulus() -> &'static RsaModulus {
+        &RSA4096_MODULUS
+    }
+}
+
+/// RSA-4096 group implementation. Like `Rsa2048`, it treats `x` and `-x` as the same element.
+pub type Rsa4096 = Rsa<Rsa4096Modulus>;
+
+/// An RSA 4096 group element.
+pub type Rsa4096Elem = RsaElem<Rsa4096Modulus>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::{ElemFrom, Group, UnknownOrderGroup};
+    use crate::util::int;
+
+    #[test]
+    fn test_init() {
+        assert_eq!(Rsa4096::rep().bits(), 4096);
+    }
+
+    #[test]
+    fn test_op() {
+        let a = Rsa4096::op(&Rsa4096::elem(2), &Rsa4096::elem(3));
+        assert!(a == Rsa4096::elem(6));
+        let b = Rsa4096::op(&Rsa4096::elem(-2), &Rsa4096::elem(-3));
+        assert!(b == Rsa4096::elem(6));
+    }
+
+    /// Tests that `-x` and `x` are treated as the same element.
+    #[test]
+    fn test_cosets() {
+        let modulus = Rsa4096::rsa_modulus().unwrap();
+        assert!(Rsa4096::elem(3) == Rsa4096::elem(modulus - 3));
+    }
+
+    #[test]
+    fn test_exp() {
+        let a = Rsa4096::exp(&Rsa4096::elem(2), &int(3));
+        assert!(a == Rsa4096::elem(8));
+        let b = Rsa4096::exp(&Rsa4096::elem(2), &int(4096));
+        assert!(b == Rsa4096::elem(int(1) << 4096));
+    }
+
+    #[test]
+    fn test_inv() {
+        let x = Rsa4096::elem(2);
+        let inv = Rsa4096::inv(&x);
+        assert!(Rsa4096::op(&x, &inv) == Rsa4096::id());
+    }
+}
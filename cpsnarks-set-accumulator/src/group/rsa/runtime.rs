@@ -0,0 +1,384 @@
+//! RSA group over a modulus chosen at runtime, e.g. the output of an MPC ceremony.
+//!
+//! `Rsa<M>` takes its modulus from the `ModulusSource` `M`, so every modulus is still its own type
+//! and elements of different groups cannot be mixed. Moduli are validated on construction, see
+//! `RsaModulus`. `RuntimeModulus` is a source which is installed once per process from bytes or a
+//! parameter file; fixed moduli (like `Rsa4096`) implement `ModulusSource` with a `lazy_static`.
+use super::{validate_elem, ElemError, ElemFrom, ElemToBytes, Group, UnknownOrderGroup};
+use crate::util::{int, TypeRep};
+use rug::integer::{IsPrime, Order};
+use rug::{rand::MutRandState, Integer};
+use rug_binserial::Integer as BinInteger;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::fs;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+/// Smallest modulus size (in bits) accepted by `RsaModulus`.
+pub const MIN_MODULUS_BITS: u32 = 2048;
+
+/// Moduli are rejected if they have an odd factor below this bound.
+const SMALL_FACTOR_BOUND: u32 = 1 << 16;
+
+/// Miller-Rabin rounds used to reject prime moduli.
+const PRIMALITY_REPS: u32 = 30;
+
+#[derive(Debug, PartialEq, Eq)]
+/// The different reasons a modulus can be rejected.
+pub enum RsaModulusError {
+    /// The parameter file could not be read.
+    Io(String),
+
+    /// The parameters are not a decimal (or `0x`-prefixed hexadecimal) integer.
+    Parse,
+
+    /// The modulus has fewer than `MIN_MODULUS_BITS` bits.
+    TooSmall(u32),
+
+    /// The modulus is even.
+    Even,
+
+    /// The modulus has the given small factor.
+    SmallFactor(u32),
+
+    /// The modulus is (probably) prime.
+    Prime,
+
+    /// The modulus is a perfect power.
+    PerfectPower,
+
+    /// A different modulus has already been installed in `RuntimeModulus`.
+    AlreadyInstalled,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// A validated RSA modulus.
+///
+/// Validation only rules out moduli which are obviously unsafe; it cannot tell whether someone
+/// knows the factorization, so the modulus should still come from a trusted setup.
+pub struct RsaModulus {
+    modulus: Integer,
+    half_modulus: Integer,
+}
+
+impl RsaModulus {
+    /// Validates `modulus` and wraps it.
+    pub fn new(modulus: Integer) -> Result<Self, RsaModulusError> {
+        let bits = modulus.significant_bits();
+        if modulus < 0 || bits < MIN_MODULUS_BITS {
+            return Err(RsaModulusError::TooSmall(bits));
+        }
+        if modulus.is_even() {
+            return Err(RsaModulusError::Even);
+        }
+        if let Some(p) = (3..SMALL_FACTOR_BOUND)
+            .step_by(2)
+            .find(|&p| modulus.is_divisible_u(p))
+        {
+            return Err(RsaModulusError::SmallFactor(p));
+        }
+        if modulus.is_probably_prime(PRIMALITY_REPS) != IsPrime::No {
+            return Err(RsaModulusError::Prime);
+        }
+        if modulus.is_perfect_power() {
+            return Err(RsaModulusError::PerfectPower);
+        }
+        let half_modulus = modulus.clone() / 2;
+        Ok(Self {
+            modulus,
+            half_modulus,
+        })
+    }
+
+    /// Reads a modulus from big-endian bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RsaModulusError> {
+        Self::new(Integer::from_digits(bytes, Order::MsfBe))
+    }
+
+    /// Reads a modulus from a parameter file, in the format accepted by `from_str`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, RsaModulusError> {
+        fs::read_to_string(path)
+            .map_err(|e| RsaModulusError::Io(e.to_string()))?
+            .parse()
+    }
+
+    /// The modulus itself.
+    pub fn modulus(&self) -> &Integer {
+        &self.modulus
+    }
+
+    /// Size of the modulus in bits.
+    pub fn bits(&self) -> u32 {
+        self.modulus.significant_bits()
+    }
+
+    /// Big-endian bytes of the modulus, the inverse of `from_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(&self.modulus)
+    }
+}
+
+impl FromStr for RsaModulus {
+    type Err = RsaModulusError;
+
+    /// Parses a decimal or `0x`-prefixed hexadecimal modulus. Whitespace is ignored, so the digits
+    /// can be wrapped over several lines, and lines starting with `#` are comments.
+    fn from_str(s: &str) -> Result<Self, RsaModulusError> {
+        let digits: String = s
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(str::chars)
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        let modulus = match digits.strip_prefix("0x") {
+            Some(hex) => Integer::from_str_radix(hex, 16),
+            None => Integer::from_str_radix(&digits, 10),
+        };
+        Self::new(modulus.map_err(|_| RsaModulusError::Parse)?)
+    }
+}
+
+fn to_bytes(n: &Integer) -> Vec<u8> {
+    let mut bytes = vec![0u8; n.significant_digits::<u8>()];
+    n.write_digits(&mut bytes, Order::MsfBe);
+    bytes
+}
+
+/// Supplies the modulus of `Rsa<M>`.
+pub trait ModulusSource: Clone + Debug + Eq + Hash + Send + Sync + 'static {
+    /// Returns the modulus of the group.
+    fn modulus() -> &'static RsaModulus;
+}
+
+lazy_static! {
+    static ref RUNTIME_MODULUS: RwLock<Option<&'static RsaModulus>> = RwLock::new(None);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Modulus source installed at runtime, once per process.
+pub enum RuntimeModulus {}
+
+impl RuntimeModulus {
+    /// Installs the modulus used by `RsaRuntime`. Installing the same modulus again is a no-op,
+    /// installing a different one fails.
+    pub fn install(modulus: RsaModulus) -> Result<(), RsaModulusError> {
+        let mut installed = RUNTIME_MODULUS.write().unwrap();
+        match *installed {
+            Some(current) if *current == modulus => Ok(()),
+            Some(_) => Err(RsaModulusError::AlreadyInstalled),
+            None => {
+                *installed = Some(Box::leak(Box::new(modulus)));
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the installed modulus, if any.
+    pub fn installed() -> Option<&'static RsaModulus> {
+        *RUNTIME_MODULUS.read().unwrap()
+    }
+}
+
+impl ModulusSource for RuntimeModulus {
+    /// Panics if no modulus has been installed.
+    fn modulus() -> &'static RsaModulus {
+        Self::installed().expect("no RSA modulus installed, call `RuntimeModulus::install` first")
+    }
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound = "")]
+/// RSA group modulo `M::modulus()`. Like `Rsa2048`, it treats `x` and `-x` as the same element.
+pub struct Rsa<M: ModulusSource>(PhantomData<M>);
+
+/// RSA group over the modulus installed in `RuntimeModulus`.
+pub type RsaRuntime = Rsa<RuntimeModulus>;
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound = "")]
+/// An element of `Rsa<M>`, directly wrapping a GMP integer from the `rug` crate.
+pub struct RsaElem<M: ModulusSource>(BinInteger, #[serde(skip)] PhantomData<M>);
+
+impl<M: ModulusSource> TypeRep for Rsa<M> {
+    type Rep = RsaModulus;
+    fn rep() -> &'static Self::Rep {
+        M::modulus()
+    }
+}
+
+impl<M: ModulusSource> Group for Rsa<M> {
+    type Elem = RsaElem<M>;
+    fn op_(rep: &RsaModulus, a: &RsaElem<M>, b: &RsaElem<M>) -> RsaElem<M> {
+        Self::elem(int(a.0.as_ref() * b.0.as_ref()) % &rep.modulus)
+    }
+
+    fn id_(_: &RsaModulus) -> RsaElem<M> {
+        Self::elem(1)
+    }
+
+    fn inv_(rep: &RsaModulus, x: &RsaElem<M>) -> RsaElem<M> {
+        Self::elem(x.0.as_ref().invert_ref(&rep.modulus).unwrap())
+    }
+
+    fn exp_(rep: &RsaModulus, x: &RsaElem<M>, n: &Integer) -> RsaElem<M> {
+        Self::elem(x.0.as_ref().pow_mod_ref(n, &rep.modulus).unwrap())
+    }
+
+    fn exp_secret_(rep: &RsaModulus, x: &RsaElem<M>, n: &Integer) -> RsaElem<M> {
+        // GMP's `mpz_powm_sec` requires a positive exponent.
+        if *n == 0 {
+            Self::id()
+        } else if *n < 0 {
+            Self::exp_secret_(rep, &Self::inv(x), &int(-n))
+        } else {
+            Self::elem(x.0.as_ref().secure_pow_mod_ref(n, &rep.modulus))
+        }
+    }
+}
+
+impl<M: ModulusSource, T> ElemFrom<T> for Rsa<M>
+where
+    Integer: From<T>,
+{
+    fn elem(t: T) -> RsaElem<M> {
+        let rep = Self::rep();
+        let val = int(t) % &rep.modulus;
+        let val = if val > rep.half_modulus {
+            <(Integer, Integer)>::from((-val).div_rem_euc_ref(&rep.modulus)).1
+        } else {
+            val
+        };
+        RsaElem(val.into(), PhantomData)
+    }
+}
+
+impl<M: ModulusSource> ElemToBytes for Rsa<M> {
+    fn elem_to_bytes(val: &RsaElem<M>) -> Vec<u8> {
+        to_bytes(val.0.as_ref())
+    }
+}
+
+impl<M: ModulusSource> UnknownOrderGroup for Rsa<M> {
+    fn unknown_order_elem_(_: &RsaModulus) -> RsaElem<M> {
+        Self::elem(2)
+    }
+
+    fn unknown_possibly_random_order_elem_<R: MutRandState>(
+        _: &Self::Rep,
+        rng: &mut R,
+    ) -> RsaElem<M> {
+        Self::elem(Self::order_upper_bound().random_below(rng))
+    }
+
+    fn order_upper_bound_(rep: &RsaModulus) -> Integer {
+        rep.half_modulus.clone()
+    }
+
+    fn rsa_modulus_(rep: &RsaModulus) -> Result<Integer, Integer> {
+        Ok(rep.modulus.clone())
+    }
+
+    fn validate_(rep: &RsaModulus, x: &RsaElem<M>) -> Result<(), ElemError> {
+        validate_elem(x.0.as_ref(), &rep.modulus, &rep.half_modulus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::rsa::rsa2048::RSA2048_MODULUS;
+    use crate::group::rsa::rsa3072::RSA3072_MODULUS;
+    use crate::group::Rsa2048;
+
+    #[test]
+    fn test_validate() {
+        let rsa2048 = RSA2048_MODULUS.clone();
+        assert_eq!(
+            RsaModulus::new(int(3233)),
+            Err(RsaModulusError::TooSmall(12))
+        );
+        assert_eq!(
+            RsaModulus::new(rsa2048.clone() * 2),
+            Err(RsaModulusError::Even)
+        );
+        assert_eq!(
+            RsaModulus::new(rsa2048.clone() * 65521),
+            Err(RsaModulusError::SmallFactor(65521))
+        );
+        let p = (int(1) << 2048).next_prime();
+        assert_eq!(RsaModulus::new(p), Err(RsaModulusError::Prime));
+        let q = (int(1) << 1024).next_prime();
+        assert_eq!(
+            RsaModulus::new(q.clone() * &q),
+            Err(RsaModulusError::PerfectPower)
+        );
+        assert_eq!(RsaModulus::new(rsa2048).unwrap().bits(), 2048);
+    }
+
+    #[test]
+    fn test_parse() {
+        let modulus = RsaModulus::new(RSA2048_MODULUS.clone()).unwrap();
+        assert_eq!(
+            RsaModulus::from_bytes(&modulus.to_bytes()),
+            Ok(modulus.clone())
+        );
+        let hex = format!("# comment\n0x{}\n", RSA2048_MODULUS.to_string_radix(16));
+        assert_eq!(hex.parse::<RsaModulus>(), Ok(modulus.clone()));
+        let decimal = RSA2048_MODULUS.to_string();
+        let (head, tail) = decimal.split_at(300);
+        let wrapped = format!("{}\n  {}\n", head, tail);
+        assert_eq!(wrapped.parse::<RsaModulus>(), Ok(modulus));
+        assert_eq!(
+            "0xnot a modulus".parse::<RsaModulus>(),
+            Err(RsaModulusError::Parse)
+        );
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join("accumulator-test-rsa-modulus.txt");
+        fs::write(&path, RSA2048_MODULUS.to_string()).unwrap();
+        let modulus = RsaModulus::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(modulus.modulus(), &*RSA2048_MODULUS);
+        assert!(matches!(
+            RsaModulus::from_file(&path),
+            Err(RsaModulusError::Io(_))
+        ));
+    }
+
+    /// The runtime group over the RSA-2048 modulus agrees with `Rsa2048`.
+    #[test]
+    fn test_runtime() {
+        let modulus = RsaModulus::new(RSA2048_MODULUS.clone()).unwrap();
+        RuntimeModulus::install(modulus.clone()).unwrap();
+        RuntimeModulus::install(modulus).unwrap();
+        let other = RsaModulus::new(RSA3072_MODULUS.clone()).unwrap();
+        assert_eq!(
+            RuntimeModulus::install(other),
+            Err(RsaModulusError::AlreadyInstalled)
+        );
+
+        let a = RsaRuntime::op(&RsaRuntime::elem(-2), &RsaRuntime::elem(-3));
+        assert!(a == RsaRuntime::elem(6));
+        assert!(RsaRuntime::elem(3) == RsaRuntime::elem(RSA2048_MODULUS.clone() - 3));
+        let x = RsaRuntime::elem(2);
+        assert!(RsaRuntime::op(&x, &RsaRuntime::inv(&x)) == RsaRuntime::id());
+
+        let n = int(4096);
+        assert_eq!(
+            RsaRuntime::elem_to_bytes(&RsaRuntime::exp(&x, &n)),
+            Rsa2048::elem_to_bytes(&Rsa2048::exp(&Rsa2048::elem(2), &n))
+        );
+        assert_eq!(
+            RsaRuntime::rsa_modulus().unwrap(),
+            Rsa2048::rsa_modulus().unwrap()
+        );
+    }
+}
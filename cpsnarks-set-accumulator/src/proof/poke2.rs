@@ -1,11 +1,12 @@
 //! Non-Interactive Proofs of Knowledge of Exponent (NI-PoKE2). See BBF (pages 10 and 42) for
 //! details.
-use crate::group::UnknownOrderGroup;
+use crate::group::{ElemError, UnknownOrderGroup};
 use crate::hash::{blake2b, hash_to_prime};
 use rug::Integer;
+use serde::Serialize;
 
 #[allow(non_snake_case)]
-#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize)]
 /// Struct for NI-PoKE2.
 pub struct Poke2<G: UnknownOrderGroup> {
     z: G::Elem,
@@ -39,6 +40,20 @@ impl<G: UnknownOrderGroup> Poke2<G> {
         let rhs = G::op(result, &G::exp(&z, &alpha));
         lhs == rhs
     }
+
+    /// Returns the prime challenge `l` and the residue `r = exp mod l` committed to by the proof.
+    ///
+    /// Allows a verifier who knows `exp` to check that the proven exponent is `exp`.
+    pub fn residue(&self, base: &G::Elem, result: &G::Elem) -> (Integer, &Integer) {
+        let l = hash_to_prime(&(base, result, &self.z));
+        (l, &self.r)
+    }
+
+    /// Checks the group elements of the proof with `UnknownOrderGroup::validate`.
+    pub fn validate(&self) -> Result<(), ElemError> {
+        G::validate(&self.z)?;
+        G::validate(&self.Q)
+    }
 }
 
 #[cfg(test)]
@@ -82,6 +97,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_poke2_validate() {
+        let base = Rsa2048::unknown_order_elem();
+        let exp = int(20);
+        let result = Rsa2048::exp(&base, &exp);
+        let proof = Poke2::<Rsa2048>::prove(&base, &exp, &result);
+        assert_eq!(proof.validate(), Ok(()));
+
+        // zero is not a canonical element
+        let invalid = Poke2 {
+            z: Rsa2048::elem(0),
+            ..proof.clone()
+        };
+        assert_eq!(invalid.validate(), Err(ElemError::NotCanonical));
+        let invalid = Poke2 {
+            Q: Rsa2048::elem(0),
+            ..proof
+        };
+        assert_eq!(invalid.validate(), Err(ElemError::NotCanonical));
+    }
+
     #[test]
     fn test_poke2_negative() {
         let base = Rsa2048::elem(2);
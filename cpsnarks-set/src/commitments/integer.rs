@@ -38,7 +38,10 @@ impl<G: ConvertibleUnknownOrderGroup> Commitment for IntegerCommitment<G> {
         value: &Integer,
         randomness: &Integer,
     ) -> Result<Self::Instance, CommitmentError> {
-        Ok(G::op(&G::exp(&self.g, value), &G::exp(&self.h, randomness)))
+        Ok(G::op(
+            &G::exp_secret(&self.g, value),
+            &G::exp_secret(&self.h, randomness),
+        ))
     }
 
     fn open(
@@ -82,11 +82,12 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Protocol<G, P> {
                 + self.crs.parameters.hash_to_prime_bits) as u32,
         ));
         let r_e = random_symmetric_range(rng1, &r_e_range);
-        let r_r_range: Integer = G::order_upper_bound() / 2
-            * Integer::from(Integer::u_pow_u(
-                2,
-                (self.crs.parameters.security_zk + self.crs.parameters.security_soundness) as u32,
-            ));
+        // the randomness of the integer commitment may exceed the order bound by security_zk bits
+        // (to be statistically hiding), the mask adds security_zk bits of slack on top of c * r
+        let r_r_range = Integer::from(1)
+            << (G::order_upper_bound().significant_bits()
+                + (2 * self.crs.parameters.security_zk + self.crs.parameters.security_soundness)
+                    as u32);
         let r_r = random_symmetric_range(rng1, &r_r_range);
         assert!(self.crs.parameters.field_size_bits as usize >= P::ScalarField::size_in_bits());
         let r_r_q_field = P::ScalarField::rand(rng2);
@@ -120,9 +121,26 @@ impl<G: ConvertibleUnknownOrderGroup, P: CurvePointProjective> Protocol<G, P> {
         statement: &Statement<G, P>,
     ) -> Result<(), VerificationError> {
         let message1 = prover_channel.receive_message1()?;
+        if G::validate(&message1.alpha1).is_err() {
+            return Err(VerificationError::VerificationFailed);
+        }
         let c = prover_channel.generate_and_send_challenge()?;
         let message2 = prover_channel.receive_message2()?;
 
+        // honest responses are the masks shifted by c times the witness,
+        // reject anything larger before exponentiating
+        let s_e_bits = (self.crs.parameters.security_zk
+            + self.crs.parameters.security_soundness
+            + self.crs.parameters.hash_to_prime_bits
+            + 1) as u32;
+        let s_r_bits = G::order_upper_bound().significant_bits()
+            + (2 * self.crs.parameters.security_zk + self.crs.parameters.security_soundness + 1)
+                as u32;
+        if message2.s_e.significant_bits() > s_e_bits || message2.s_r.significant_bits() > s_r_bits
+        {
+            return Err(VerificationError::VerificationFailed);
+        }
+
         let commitment2 = self
             .crs
             .integer_commitment_parameters
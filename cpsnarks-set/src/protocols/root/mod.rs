@@ -83,7 +83,7 @@ impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
         let r_3 = random_symmetric_range(rng, &(G::order_upper_bound() / Integer::from(2)));
         let c_w = G::op(
             &witness.w,
-            &G::exp(&self.crs.integer_commitment_parameters.h, &r_2),
+            &G::exp_secret(&self.crs.integer_commitment_parameters.h, &r_2),
         );
         let c_r = self.crs.integer_commitment_parameters.commit(&r_2, &r_3)?;
 
@@ -132,7 +132,7 @@ impl<G: ConvertibleUnknownOrderGroup> Protocol<G> {
             &G::inv(&self.crs.integer_commitment_parameters.g),
         );
         let alpha4 = G::op(
-            &G::exp(&message1.c_r, &r_e),
+            &G::exp_secret(&message1.c_r, &r_e),
             &integer_commitment_alpha4.commit(&r_delta, &r_beta)?,
         );
         let message2 = Message2::<G> {
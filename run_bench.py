@@ -5,7 +5,7 @@ import json
 import fractions
 import subprocess
 
-def bench(op, algo, keys=1, exts=1, iters=10):
+def bench(op, algo, keys=1, exts=1, iters=10, poe='exp'):
     env = os.environ
     env['RUSTFLAGS'] = '-C target-cpu=native'
     env['BENCH_KEYS'] = str(keys)
@@ -13,6 +13,7 @@ def bench(op, algo, keys=1, exts=1, iters=10):
     env['BENCH_ITERS'] = str(iters)
     env['BENCH_OP'] = op
     env['BENCH_ALGO'] = algo
+    env['BENCH_POE'] = poe
     cmd = ['cargo', 'run', '--release']
     done = subprocess.run(
         cmd,
@@ -57,10 +58,11 @@ def bench(op, algo, keys=1, exts=1, iters=10):
     return res
 
 
-def run_bench(group, op, keys, exts):
+def run_bench(group, op, keys, exts, poe='exp'):
     assert op in ('sign', 'verify')
-    assert group in ('class', 'rsa')
-    return bench(op, algo=group, keys=keys, exts=exts)
+    assert group in ('class', 'rsa', 'rsa4096', 'rsa-file')
+    assert poe in ('trivial', 'exp', 'wesolowski', 'pietrzak', 'poke')
+    return bench(op, algo=group, keys=keys, exts=exts, poe=poe)
 
 if __name__ == '__main__':
 
@@ -68,6 +70,7 @@ if __name__ == '__main__':
 
     group = sys.argv[1]
     op = sys.argv[2]
+    poe = sys.argv[3] if len(sys.argv) > 3 else 'exp'
 
     val_exts = [1, 2, 4, 8]
     val_keys = [1 << i for i in range(12)]
@@ -80,9 +83,13 @@ if __name__ == '__main__':
             keys_per_ext = math.ceil(keys / exts)
             if keys_per_ext * exts > keys:
                 continue
-            out = run_bench(group, op, keys_per_ext, exts)
-            print(group, op, 'exts:', exts, 'keys:', keys_per_ext * exts, 'res:', out)
+            out = run_bench(group, op, keys_per_ext, exts, poe)
+            print(group, op, poe, 'exts:', exts, 'keys:', keys_per_ext * exts, 'res:', out)
             results[keys] = out
 
-        with open('%s-%s-%s.json' % (group, op, exts), 'w') as f:
+        name = '%s-%s-%s' % (group, op, exts)
+        if poe != 'exp':
+            name = '%s-%s' % (name, poe)
+
+        with open('%s.json' % name, 'w') as f:
             json.dump(results, f)
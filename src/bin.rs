@@ -1,3 +1,4 @@
+use extend_sig::params::SecurityParameters;
 use extend_sig::unknown::exppok::ProofOfExp;
 use extend_sig::*;
 use std::time::{Duration, SystemTime};
@@ -15,9 +16,10 @@ use curve25519_dalek::scalar::Scalar;
 
 use serde::Serialize;
 
-use accumulator::group::{ClassGroup, Rsa3072};
+use accumulator::group::{ClassGroup, Rsa3072, Rsa4096, RsaModulus, RsaRuntime, RuntimeModulus};
 
 use std::env;
+use std::process;
 
 use bincode;
 
@@ -32,7 +34,7 @@ fn bench_sign<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>>(
     println!("num_extend: {}", num_extend);
     println!("num_keys: {}", num_keys);
 
-    let mut ctx = Context::<G>::setup(msg);
+    let mut ctx = Context::<G>::setup(SecurityParameters::for_group::<G>(), msg).unwrap();
     let sk = SigningKey::new();
     let pk = sk.pk();
 
@@ -76,7 +78,7 @@ fn bench_verify<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>>(
     println!("num_extend: {}", num_extend);
     println!("num_keys: {}", num_keys);
 
-    let mut ctx = Context::<G>::setup(msg);
+    let mut ctx = Context::<G>::setup(SecurityParameters::for_group::<G>(), msg).unwrap();
 
     let sk = SigningKey::new();
     let pk = sk.pk();
@@ -118,7 +120,7 @@ fn bench_verify<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>>(
     println!("duration: {}", elapsed.as_nanos());
 }
 
-fn bench_verify_args<G: ConvertibleUnknownOrderGroup>() {
+fn bench_verify_args<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>>() {
     let keys: usize = env::var("BENCH_KEYS")
         .unwrap_or("1".to_string())
         .parse()
@@ -131,10 +133,10 @@ fn bench_verify_args<G: ConvertibleUnknownOrderGroup>() {
         .unwrap_or("1".to_string())
         .parse()
         .unwrap();
-    bench_verify::<G, unknown::exppok::Proof<_>>(iters, exts, keys);
+    bench_verify::<G, E>(iters, exts, keys);
 }
 
-fn bench_sign_args<G: ConvertibleUnknownOrderGroup>() {
+fn bench_sign_args<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>>() {
     let keys: usize = env::var("BENCH_KEYS")
         .unwrap_or("1".to_string())
         .parse()
@@ -147,18 +149,64 @@ fn bench_sign_args<G: ConvertibleUnknownOrderGroup>() {
         .unwrap_or("1".to_string())
         .parse()
         .unwrap();
-    bench_sign::<G, unknown::exppok::Proof<_>>(iters, exts, keys);
+    bench_sign::<G, E>(iters, exts, keys);
+}
+
+// unknown value of an environment variable: print the accepted ones and exit
+fn usage(var: &str, value: &str, accepted: &[&str]) -> ! {
+    eprintln!(
+        "unknown {}: {} (expected one of: {})",
+        var,
+        value,
+        accepted.join(", ")
+    );
+    process::exit(1)
+}
+
+fn bench_op<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>>(op: &str) {
+    match op {
+        "sign" => bench_sign_args::<G, E>(),
+        "verify" => bench_verify_args::<G, E>(),
+        _ => usage("BENCH_OP", op, &["sign", "verify"]),
+    }
+}
+
+fn bench_poe<G: ConvertibleUnknownOrderGroup>(op: &str) {
+    let poe = env::var("BENCH_POE").unwrap_or("exp".to_string());
+
+    println!("poe: {}", poe);
+
+    match poe.as_ref() {
+        "trivial" => bench_op::<G, unknown::exppok::TrivialProof>(op),
+        "exp" => bench_op::<G, unknown::exppok::Proof<_>>(op),
+        "wesolowski" => bench_op::<G, unknown::exppok::WesolowskiProof<_>>(op),
+        "pietrzak" => bench_op::<G, unknown::exppok::PietrzakProof<_>>(op),
+        "poke" => bench_op::<G, unknown::exppok::PokeProof<_>>(op),
+        _ => usage(
+            "BENCH_POE",
+            &poe,
+            &["trivial", "exp", "wesolowski", "pietrzak", "poke"],
+        ),
+    }
 }
 
 fn main() {
     let algo = env::var("BENCH_ALGO").unwrap();
     let op = env::var("BENCH_OP").unwrap();
 
-    match (algo.as_ref(), op.as_ref()) {
-        ("rsa", "sign") => bench_sign_args::<Rsa3072>(),
-        ("rsa", "verify") => bench_verify_args::<Rsa3072>(),
-        ("class", "sign") => bench_sign_args::<ClassGroup>(),
-        ("class", "verify") => bench_verify_args::<ClassGroup>(),
-        _ => unimplemented!(),
+    match algo.as_ref() {
+        "rsa" => bench_poe::<Rsa3072>(&op),
+        "rsa4096" => bench_poe::<Rsa4096>(&op),
+        "rsa-file" => {
+            let path = env::var("BENCH_MODULUS").unwrap();
+            RuntimeModulus::install(RsaModulus::from_file(path).unwrap()).unwrap();
+            bench_poe::<RsaRuntime>(&op)
+        }
+        "class" => bench_poe::<ClassGroup>(&op),
+        _ => usage(
+            "BENCH_ALGO",
+            &algo,
+            &["rsa", "rsa4096", "rsa-file", "class"],
+        ),
     }
 }
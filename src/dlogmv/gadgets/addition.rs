@@ -3,22 +3,27 @@ use super::*;
 use bulletproofs::r1cs::*;
 use curve25519_dalek::scalar::Scalar;
 
-pub struct Gadget();
+use std::marker::PhantomData;
 
-pub struct Witness {
-    in1: CurvePoint,
-    in2: CurvePoint,
-    out: CurvePoint,
+/// Number of multipliers used by a single addition
+pub const MULTIPLIERS: usize = 6;
+
+pub struct Gadget<P: EdwardsParams = curve::Jabberwock>(PhantomData<P>);
+
+pub struct Witness<P: EdwardsParams = curve::Jabberwock> {
+    in1: Point<P>,
+    in2: Point<P>,
+    out: Point<P>,
 }
 
-impl Witness {
-    pub fn output(&self) -> CurvePoint {
+impl<P: EdwardsParams> Witness<P> {
+    pub fn output(&self) -> Point<P> {
         self.out
     }
 }
 
-impl Gadget {
-    pub fn witness(in1: CurvePoint, in2: CurvePoint) -> Witness {
+impl<P: EdwardsParams> Gadget<P> {
+    pub fn witness(in1: Point<P>, in2: Point<P>) -> Witness<P> {
         Witness {
             in1,
             in2,
@@ -28,13 +33,13 @@ impl Gadget {
 
     pub fn gadget<CS: ConstraintSystem>(
         cs: &mut CS,
-        witness: Option<&Witness>,
+        witness: Option<&Witness<P>>,
     ) -> Result<(CurveVariable, CurveVariable, CurveVariable), R1CSError> {
         let (m1, m2, m3, m4) = match witness {
             Some(w) => {
                 let a = w.in1.x * w.in2.y;
                 let b = w.in1.y * w.in2.x;
-                let c = curve::param_d() * a * b;
+                let c = P::param_d() * a * b;
                 let m1 = cs.allocate_multiplier(Some((w.in1.x, w.in2.y)))?;
                 let m2 = cs.allocate_multiplier(Some((w.in1.y, w.in2.x)))?;
                 let m3 = cs.allocate_multiplier(Some((Scalar::one() + c, w.out.x)))?;
@@ -57,7 +62,7 @@ impl Gadget {
         let (one_m_c, out_y, left2) = m4;
 
         let (_, _, t) = cs.multiply(in1_x + in1_y, in2_y - in2_x);
-        let (_, _, c) = cs.multiply(curve::param_d() * a, b.into());
+        let (_, _, c) = cs.multiply(P::param_d() * a, b.into());
 
         cs.constrain(one() + c - one_p_c);
         cs.constrain(one() - c - one_m_c);
@@ -71,7 +76,7 @@ impl Gadget {
             let (_, _, x2) = cs.multiply(out_x.into(), out_x.into());
             let (_, _, y2) = cs.multiply(out_y.into(), out_y.into());
             let (_, _, x2y2) = cs.multiply(x2.into(), y2.into());
-            cs.constrain((x2 + y2) - (one() + curve::param_d() * x2y2));
+            cs.constrain((x2 + y2) - (one() + P::param_d() * x2y2));
         };
 
         Ok((
@@ -81,3 +86,43 @@ impl Gadget {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn test_malicious() {
+        let mut rng = thread_rng();
+        let p1 = curve::Fp::random(&mut rng) * curve::g0();
+        let p2 = curve::Fp::random(&mut rng) * curve::g0();
+
+        // claimed results
+        let claims = [p1 + p2, p1 + p2 + curve::g0(), p1, p2 + p2];
+
+        malicious::check_soundness(
+            claims.len(),
+            |i, prover| {
+                let witness = Witness {
+                    in1: p1,
+                    in2: p2,
+                    out: claims[i],
+                };
+                let (in1, in2, out) = Gadget::gadget(prover, Some(&witness))?;
+                in1.constant(prover, &p1)?;
+                in2.constant(prover, &p2)?;
+                out.constant(prover, &claims[i])?;
+                Ok(vec![])
+            },
+            |i, verifier, _| {
+                let (in1, in2, out) = Gadget::<curve::Jabberwock>::gadget(verifier, None)?;
+                in1.constant(verifier, &p1)?;
+                in2.constant(verifier, &p2)?;
+                out.constant(verifier, &claims[i])?;
+                Ok(())
+            },
+        );
+    }
+}
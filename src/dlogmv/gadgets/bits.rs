@@ -285,4 +285,33 @@ mod tests {
 
         verifier.verify(&proof, &pc_gens, &bp_gens).unwrap();
     }
+
+    #[test]
+    fn test_malicious() {
+        let scalar = Scalar::random(&mut OsRng);
+        let other = Scalar::random(&mut OsRng);
+        let gadget = Gadget::new_size(253);
+
+        malicious::check_soundness(
+            3,
+            |i, prover| {
+                let mut bits = scalar_to_bits(scalar);
+                match i {
+                    0 => (),
+                    1 => bits[17] = !bits[17],         // flip a single bit
+                    _ => bits = scalar_to_bits(other), // decomposition of another value
+                };
+                let (comm, value) = prover.commit(scalar, Scalar::random(&mut OsRng));
+                let out: LinearCombination = gadget.gadget(prover, Some(&bits[..]))?.into();
+                prover.constrain(out - value);
+                Ok(vec![comm])
+            },
+            |_, verifier, comms| {
+                let value = verifier.commit(comms[0]);
+                let out: LinearCombination = gadget.gadget(verifier, None)?.into();
+                verifier.constrain(out - value);
+                Ok(())
+            },
+        );
+    }
 }
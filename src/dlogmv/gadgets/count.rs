@@ -0,0 +1,223 @@
+use bulletproofs::r1cs::*;
+
+use curve25519_dalek::scalar::Scalar;
+
+use merlin::Transcript;
+
+use std::ops::Sub;
+
+use super::*;
+
+/// Size of a circuit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Count {
+    pub multipliers: usize, // multiplication gates
+    pub constraints: usize, // linear constraints
+    pub committed: usize,   // committed (high-level) variables
+}
+
+impl Sub for Count {
+    type Output = Count;
+
+    fn sub(self, other: Count) -> Count {
+        Count {
+            multipliers: self.multipliers - other.multipliers,
+            constraints: self.constraints - other.constraints,
+            committed: self.committed - other.committed,
+        }
+    }
+}
+
+impl Count {
+    /// Number of generators needed to prove/verify a circuit of this size
+    pub fn gens_capacity(&self) -> usize {
+        self.multipliers.next_power_of_two()
+    }
+}
+
+/// A constraint system which only counts the multipliers, constraints and commitments:
+/// it does not hold assignments and can be used to measure the size of any gadget
+/// (in the same way as a verifier).
+pub struct Counter {
+    transcript: Transcript,
+    count: Count,
+    pending: Option<usize>, // index of the multiplier with a free right input
+}
+
+impl Counter {
+    pub fn new() -> Self {
+        Self {
+            transcript: Transcript::new(b"Counter"),
+            count: Count::default(),
+            pending: None,
+        }
+    }
+
+    pub fn commit(&mut self) -> Variable {
+        let i = self.count.committed;
+        self.count.committed += 1;
+        Variable::Committed(i)
+    }
+
+    pub fn count(&self) -> Count {
+        self.count
+    }
+
+    /// Counts the size of the circuit constrained by `f` (on top of what is already counted)
+    pub fn measure<F>(&mut self, f: F) -> Result<Count, R1CSError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), R1CSError>,
+    {
+        let before = self.count;
+        f(self)?;
+        Ok(self.count - before)
+    }
+
+    fn next_multiplier(&mut self) -> usize {
+        let i = self.count.multipliers;
+        self.count.multipliers += 1;
+        i
+    }
+}
+
+impl ConstraintSystem for Counter {
+    fn transcript(&mut self) -> &mut Transcript {
+        &mut self.transcript
+    }
+
+    fn multiply(
+        &mut self,
+        _left: LinearCombination,
+        _right: LinearCombination,
+    ) -> (Variable, Variable, Variable) {
+        let i = self.next_multiplier();
+
+        // the inputs are constrained to the linear combinations
+        self.count.constraints += 2;
+        (
+            Variable::MultiplierLeft(i),
+            Variable::MultiplierRight(i),
+            Variable::MultiplierOutput(i),
+        )
+    }
+
+    fn allocate(&mut self, _: Option<Scalar>) -> Result<Variable, R1CSError> {
+        match self.pending {
+            Some(i) => {
+                self.pending = None;
+                Ok(Variable::MultiplierRight(i))
+            }
+            None => {
+                let i = self.next_multiplier();
+                self.pending = Some(i);
+                Ok(Variable::MultiplierLeft(i))
+            }
+        }
+    }
+
+    fn allocate_multiplier(
+        &mut self,
+        _: Option<(Scalar, Scalar)>,
+    ) -> Result<(Variable, Variable, Variable), R1CSError> {
+        let i = self.next_multiplier();
+        Ok((
+            Variable::MultiplierLeft(i),
+            Variable::MultiplierRight(i),
+            Variable::MultiplierOutput(i),
+        ))
+    }
+
+    fn metrics(&self) -> Metrics {
+        Metrics {
+            multipliers: self.count.multipliers,
+            constraints: self.count.constraints,
+            phase_one_constraints: self.count.constraints,
+            phase_two_constraints: 0,
+        }
+    }
+
+    fn constrain(&mut self, _: LinearCombination) {
+        self.count.constraints += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_gadgets() {
+        let mut cs = Counter::new();
+
+        // bit decomposition
+        let decomp = bits::Gadget::new_size(curve::FP_INNER_BITS);
+        let count = cs
+            .measure(|cs| decomp.gadget_inner(cs, None).map(|_| ()))
+            .unwrap();
+        println!("bits ({}): {:?}", curve::FP_INNER_BITS, count);
+        assert_eq!(count.multipliers, curve::FP_INNER_BITS);
+
+        // addition
+        let add = cs
+            .measure(|cs| addition::Gadget::<curve::Jabberwock>::gadget(cs, None).map(|_| ()))
+            .unwrap();
+        println!("addition: {:?}", add);
+        assert!(add.multipliers >= addition::MULTIPLIERS); // more in debug builds
+
+        // permissible
+        let permissible = permissible::Gadget::<curve::Jabberwock>::new();
+        let count = cs
+            .measure(|cs| permissible.gadget(cs, None).map(|_| ()))
+            .unwrap();
+        println!("permissible: {:?}", count);
+
+        // window lookups and fixed-base exponentiation for every window size
+        for size in window::MIN_WINDOW_SIZE..=window::MAX_WINDOW_SIZE {
+            let win = window::EdwardsWindow::new(curve::param_d(), curve::g0(), size);
+            let bits: Vec<bits::BitVariable> = (0..size)
+                .map(|_| bits::BitVariable::free(&mut cs).unwrap())
+                .collect();
+            let lookup = CurveVariable::free(&mut cs).unwrap();
+            let count = cs
+                .measure(|cs| win.gadget(cs, None, lookup, &bits[..]))
+                .unwrap();
+            println!("window ({} bits): {:?}", size, count);
+            assert_eq!(count.multipliers, window::multipliers(size));
+
+            let fixexp = fixexp::Gadget::new_window(curve::g0(), size);
+            let scalar = decomp.gadget_inner(&mut cs, None).unwrap();
+            let count = cs
+                .measure(|cs| fixexp.gadget(cs, &scalar, None).map(|_| ()))
+                .unwrap();
+            println!("fixexp ({} bits): {:?}", size, count);
+
+            // lookups in windows padded with zeroes are cheaper
+            let windows = (curve::FP_INNER_BITS + size - 1) / size;
+            assert!(
+                count.multipliers
+                    <= windows * window::multipliers(size) + (windows - 1) * add.multipliers
+            );
+        }
+    }
+
+    #[test]
+    fn test_count_commit() {
+        let mut cs = Counter::new();
+        let count = cs
+            .measure(|cs| {
+                let v = cs.commit();
+                let (_, _, o) = cs.multiply(v.into(), v.into());
+                cs.constrain(o - v);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            count,
+            Count {
+                multipliers: 1,
+                constraints: 3,
+                committed: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,610 @@
+//! Twisted Edwards curves (with a = 1) over the Ristretto scalar field:
+//!
+//! x^2 + y^2 = 1 + d x^2 y^2
+//!
+//! A curve is described by an `EdwardsParams` type,
+//! which is generated from the parameters computed by Sage using `edwards_curve!`.
+
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::ops::{Add, Neg, Sub};
+
+use curve25519_dalek::scalar::Scalar;
+use num_traits::Zero;
+
+use sha2::Digest;
+use sha2::Sha512;
+
+use serde::{Deserialize, Serialize};
+
+use rand_core::RngCore;
+
+/// Parameters of a twisted Edwards curve over the Ristretto scalar field
+pub trait EdwardsParams: Copy + Clone + Debug + Default + Eq + PartialEq + 'static {
+    /// Scalar field of the curve (i.e. the order of the prime order subgroup)
+    type Fp: Copy + Clone + Debug;
+
+    /// Size of the scalar field in bits
+    const FP_BITS: usize;
+
+    /// The curve constant d
+    fn param_d() -> Scalar;
+
+    /// Little-endian bit decomposition of a scalar
+    fn fp_bits(s: &Self::Fp) -> Vec<bool>;
+
+    /// Uniformly random scalar
+    fn fp_random<R: RngCore>(rng: &mut R) -> Self::Fp;
+}
+
+/// Point on the curve described by `P`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct Point<P: EdwardsParams> {
+    pub x: Scalar,
+    pub y: Scalar,
+    #[serde(skip)]
+    curve: PhantomData<P>,
+}
+
+// Do not edit manually!
+// This function was automatically generated by Sage (and cargo fmt).
+// square root in the Ristretto scalar field (the base field of every curve)
+fn sqrt(s: Scalar) -> Option<Scalar> {
+    let acc = s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    let acc = acc * s;
+    let acc = acc * acc;
+    if acc * acc == s {
+        Some(acc)
+    } else {
+        None
+    }
+}
+
+impl<P: EdwardsParams> Point<P> {
+    pub fn new(x: Scalar, y: Scalar) -> Self {
+        Point {
+            x,
+            y,
+            curve: PhantomData,
+        }
+    }
+
+    pub fn on_curve(&self) -> bool {
+        let x2 = self.x * self.x;
+        let y2 = self.y * self.y;
+        x2 + y2 == Scalar::one() + P::param_d() * x2 * y2
+    }
+
+    pub fn hash(val: &[u8]) -> Self {
+        fn hash_cnt<P: EdwardsParams>(val: &[u8], cnt: u32) -> Option<Point<P>> {
+            // hash (val || cnt)
+            let mut hasher = Sha512::default();
+            hasher.update(val);
+            hasher.update(&cnt.to_le_bytes());
+
+            // y coordinate is hash
+            let y = Scalar::from_hash(hasher);
+
+            // isolate x in x^2 + y^2 = 1 + d x^2 y^2
+            let y2 = y * y;
+
+            // sqrt(y^2 - 1)
+            let y2_1 = y2 - Scalar::one();
+            let y2_1_sq = sqrt(y2_1)?;
+
+            // sqrt(d * y^2 - 1)
+            let dy2_1 = P::param_d() * y2 - Scalar::one();
+            let dy2_1_sq = sqrt(dy2_1)?;
+
+            // x = sqrt(y^2 - 1) / sqrt(d * y^2 - 1)
+            let x = y2_1_sq * dy2_1_sq.invert();
+            Some(Point::new(x, y))
+        }
+
+        let mut cnt: u32 = 0;
+        loop {
+            match hash_cnt(val, cnt) {
+                None => {
+                    cnt += 1;
+                }
+                Some(p) => {
+                    debug_assert!(p.on_curve());
+                    break p;
+                }
+            }
+        }
+    }
+
+    /// Scalar multiplication (double-and-add)
+    pub fn scale(&self, s: &P::Fp) -> Self {
+        let mut res = Zero::zero();
+        let mut pow = *self;
+        for bit in P::fp_bits(s) {
+            if bit {
+                res = res + pow;
+            }
+            pow = pow + pow;
+        }
+        res
+    }
+}
+
+impl<P: EdwardsParams> Zero for Point<P> {
+    fn zero() -> Self {
+        Point::new(Scalar::zero(), Scalar::one())
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == Point::zero()
+    }
+}
+
+impl<P: EdwardsParams> Add for Point<P> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        debug_assert!(self.on_curve());
+        debug_assert!(other.on_curve());
+        let p = P::param_d() * self.x * self.y * other.x * other.y;
+        let x = (self.x * other.y + other.x * self.y) * (Scalar::one() + p).invert();
+        let y = (self.y * other.y - self.x * other.x) * (Scalar::one() - p).invert();
+        let p = Point::new(x, y);
+        debug_assert!(p.on_curve());
+        p
+    }
+}
+
+impl<P: EdwardsParams> Sub for Point<P> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + (-other)
+    }
+}
+
+impl<P: EdwardsParams> Neg for Point<P> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Point::new(-self.x, self.y)
+    }
+}
+
+/// Defines a curve from the parameters computed by Sage (see `curve/curve.sage`):
+/// the scalar field (using `gridiron::fp31!`), the `EdwardsParams` type,
+/// the `CurvePoint` alias and the generators.
+macro_rules! edwards_curve {
+    (
+        params: $params:ident,
+        field: $fp_mod:ident :: $fp:ident,
+        bits: $bits:tt,
+        limbs: $limbs:tt,
+        prime: $prime:tt,
+        barrett: $barrett:tt,
+        montgomery_r: $r:tt,
+        montgomery_r2: $r2:tt,
+        m0_inv: $m0_inv:tt,
+        d: $d:expr,
+        generators: [$($g:ident = ($gx:expr, $gy:expr)),* $(,)?] $(,)?
+    ) => {
+        fp31!($fp_mod, $fp, $bits, $limbs, $prime, $barrett, $r, $r2, $m0_inv);
+
+        // size of inner field in bits
+        pub const FP_INNER_BITS: usize = $bits;
+
+        // scalar field of the inner curve
+        pub use $fp_mod::$fp as Fp;
+
+        #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+        pub struct $params;
+
+        impl $crate::dlogmv::gadgets::edwards::EdwardsParams for $params {
+            type Fp = Fp;
+
+            const FP_BITS: usize = $bits;
+
+            fn param_d() -> ::curve25519_dalek::scalar::Scalar {
+                ::curve25519_dalek::scalar::Scalar::from_bits($d)
+            }
+
+            fn fp_bits(s: &Fp) -> Vec<bool> {
+                s.iter_bit().map(|b| b.0 != 0).collect()
+            }
+
+            fn fp_random<R: ::rand_core::RngCore>(rng: &mut R) -> Fp {
+                Fp::random(rng)
+            }
+        }
+
+        // point on inner curve
+        pub type CurvePoint = $crate::dlogmv::gadgets::edwards::Point<$params>;
+
+        pub fn param_d() -> ::curve25519_dalek::scalar::Scalar {
+            <$params as $crate::dlogmv::gadgets::edwards::EdwardsParams>::param_d()
+        }
+
+        impl Fp {
+            pub fn random<R: ::rand_core::RngCore>(rng: &mut R) -> Self {
+                let mut limbs: [u8; 32] = [0; 32];
+                rng.fill_bytes(&mut limbs);
+                limbs[0] = 0;
+                limbs.into()
+            }
+        }
+
+        impl ::std::ops::Mul<CurvePoint> for Fp {
+            type Output = CurvePoint;
+
+            fn mul(self, point: CurvePoint) -> CurvePoint {
+                point.scale(&self)
+            }
+        }
+
+        $(
+            pub fn $g() -> CurvePoint {
+                CurvePoint::new(
+                    ::curve25519_dalek::scalar::Scalar::from_bits($gx),
+                    ::curve25519_dalek::scalar::Scalar::from_bits($gy),
+                )
+            }
+        )*
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use super::super::curve;
+
+    use rand_core::OsRng;
+
+    fn test_group_laws<P: EdwardsParams>(g: Point<P>) {
+        let r0 = P::fp_random(&mut OsRng);
+        let r1 = P::fp_random(&mut OsRng);
+        let p0 = g.scale(&r0);
+        let p1 = g.scale(&r1);
+
+        assert!(p0.on_curve());
+        assert_eq!(p0 + p1, p1 + p0);
+        assert_eq!(p0 + Point::zero(), p0);
+        assert_eq!(p0 - p0, Point::zero());
+        assert_eq!((p0 + p1) - p1, p0);
+    }
+
+    #[test]
+    fn test_jabberwock() {
+        test_group_laws(curve::g0());
+        test_group_laws(curve::g1());
+        test_group_laws::<curve::Jabberwock>(Point::hash(b"test"));
+    }
+
+    #[test]
+    fn test_serialize() {
+        let p = curve::g0();
+        let bs = bincode::serialize(&p).unwrap();
+        assert_eq!(bs.len(), 64);
+        assert_eq!(
+            bincode::deserialize::<curve::CurvePoint>(&bs[..]).unwrap(),
+            p
+        );
+    }
+}
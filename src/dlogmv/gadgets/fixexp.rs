@@ -2,56 +2,104 @@ use super::*;
 
 use num_traits::Zero;
 
-const WINDOWS: usize = (curve::FP_INNER_BITS + window::WINDOW_SIZE - 1) / window::WINDOW_SIZE;
-
-pub struct Witness {
-    windows: Vec<window::Witness>,
-    additions: Vec<addition::Witness>,
+pub struct Witness<P: EdwardsParams = curve::Jabberwock> {
+    windows: Vec<window::Witness<P>>,
+    additions: Vec<addition::Witness<P>>,
 }
 
-pub struct Gadget {
-    windows: Vec<window::EdwardsWindow>,
+pub struct Gadget<P: EdwardsParams = curve::Jabberwock> {
+    windows: Vec<window::EdwardsWindow<P>>,
 }
 
 /// Optimized circuit for fixed-based exponentiation
-impl Gadget {
-    pub fn new(base: CurvePoint) -> Self {
+impl<P: EdwardsParams> Gadget<P> {
+    /// Uses the window size with the fewest multipliers (the smallest one in case of ties),
+    /// see `release_multipliers`
+    pub fn new(base: Point<P>) -> Self {
+        (window::MIN_WINDOW_SIZE..=window::MAX_WINDOW_SIZE)
+            .map(|size| Self::new_window(base, size))
+            .min_by_key(|gadget| gadget.release_multipliers())
+            .unwrap()
+    }
+
+    /// Size of the circuit for every window size
+    pub fn window_counts(base: Point<P>) -> Vec<(usize, count::Count)> {
+        (window::MIN_WINDOW_SIZE..=window::MAX_WINDOW_SIZE)
+            .map(|size| (size, Self::new_window(base, size).count()))
+            .collect()
+    }
+
+    /// Size of the circuit (excluding the bit decomposition of the scalar)
+    pub fn count(&self) -> count::Count {
+        let mut cs = count::Counter::new();
+        let scalar = bits::Gadget::new_size(P::FP_BITS)
+            .gadget_inner(&mut cs, None)
+            .unwrap();
+        cs.measure(|cs| self.gadget(cs, &scalar, None).map(|_| ()))
+            .unwrap()
+    }
+
+    /// Number of multipliers of the circuit in release builds:
+    /// in tests the additions also check that their output is on the curve,
+    /// which must not change the window size chosen by `new` (prover and verifier have to agree).
+    pub fn release_multipliers(&self) -> usize {
+        let mut cs = count::Counter::new();
+        let add = cs
+            .measure(|cs| addition::Gadget::<P>::gadget(cs, None).map(|_| ()))
+            .unwrap();
+        let additions = self.windows.len() - 1;
+        self.count().multipliers - additions * (add.multipliers - addition::MULTIPLIERS)
+    }
+
+    /// Like `new`, but with an explicit window size (in bits)
+    pub fn new_window(base: Point<P>, size: usize) -> Self {
         debug_assert!(base.on_curve());
+        assert!(size >= window::MIN_WINDOW_SIZE && size <= window::MAX_WINDOW_SIZE);
+
+        let windows = (P::FP_BITS + size - 1) / size;
 
         let mut current = base;
-        let mut windows = Vec::with_capacity(WINDOWS);
-        for _ in 0..WINDOWS {
-            let win = window::EdwardsWindow::new(curve::param_d(), current);
-            current = current + win.lookup(true, true, true);
-            windows.push(win);
+        let mut table = Vec::with_capacity(windows);
+        for _ in 0..windows {
+            let win = window::EdwardsWindow::new(P::param_d(), current, size);
+            current = current + win.lookup(&vec![true; size][..]);
+            table.push(win);
         }
-        Self { windows }
+        Self { windows: table }
+    }
+
+    pub fn window_size(&self) -> usize {
+        self.windows[0].size()
     }
 
-    pub fn compute(&self, scalar: curve::Fp) -> CurvePoint {
-        let mut bits = scalar.iter_bit().map(|b| b.0);
+    // split the scalar into windows of bits (padded with zeroes)
+    fn window_bits(&self, scalar: P::Fp) -> Vec<Vec<bool>> {
+        let size = self.window_size();
+        let mut bits = P::fp_bits(&scalar).into_iter();
+        let windows = self
+            .windows
+            .iter()
+            .map(|_| (0..size).map(|_| bits.next().unwrap_or(false)).collect())
+            .collect();
+        debug_assert!(bits.next().is_none());
+        windows
+    }
+
+    pub fn compute(&self, scalar: P::Fp) -> Point<P> {
         let mut point = Zero::zero();
-        for win in self.windows.iter() {
-            let b0 = bits.next().unwrap_or(0) != 0;
-            let b1 = bits.next().unwrap_or(0) != 0;
-            let b2 = bits.next().unwrap_or(0) != 0;
-            point = win.lookup(b0, b1, b2) + point;
+        for (win, bits) in self.windows.iter().zip(self.window_bits(scalar)) {
+            point = win.lookup(&bits[..]) + point;
         }
-        debug_assert!(bits.next().is_none());
         point
     }
 
-    pub fn witness(&self, scalar: curve::Fp) -> (Witness, CurvePoint) {
-        let mut bits = scalar.iter_bit().map(|b| b.0 != 0);
+    pub fn witness(&self, scalar: P::Fp) -> (Witness<P>, Point<P>) {
         let mut point = None;
-        let mut windows = Vec::with_capacity(self.windows.len() - 1);
-        let mut additions = Vec::with_capacity(self.windows.len());
-        for (i, window) in self.windows.iter().enumerate() {
+        let mut windows = Vec::with_capacity(self.windows.len());
+        let mut additions = Vec::with_capacity(self.windows.len() - 1);
+        for (window, bits) in self.windows.iter().zip(self.window_bits(scalar)) {
             // do lookup
-            let b0 = bits.next().unwrap_or(false);
-            let b1 = bits.next().unwrap_or(false);
-            let b2 = bits.next().unwrap_or(false);
-            let win = window.witness(b0, b1, b2);
+            let win = window.witness(&bits[..]);
 
             // do addition
             point = Some(match point {
@@ -74,10 +122,14 @@ impl Gadget {
         &self,
         cs: &mut CS,
         scalar: &bits::Decompose, // bit decomposition of scalar
-        witness: Option<&Witness>,
+        witness: Option<&Witness<P>>,
     ) -> Result<CurveVariable, R1CSError> {
+        let size = self.window_size();
+
         // traverse bits, least significant to most
         let mut bits = scalar.iter_bits();
+        let mut next_bits =
+            || -> Vec<bits::BitVariable> { (0..size).map(|_| bits.next().unwrap()).collect() };
 
         // do all additions
         let mut additions = Vec::with_capacity(self.windows.len() - 1);
@@ -93,9 +145,7 @@ impl Gadget {
             cs,
             witness.map(|w| &w.windows[0]),
             additions[0].0,
-            bits.next().unwrap(),
-            bits.next().unwrap(),
-            bits.next().unwrap(),
+            &next_bits()[..],
         )?;
 
         // constrain first right term to lookup table 2
@@ -103,9 +153,7 @@ impl Gadget {
             cs,
             witness.map(|w| &w.windows[1]),
             additions[0].1,
-            bits.next().unwrap(),
-            bits.next().unwrap(),
-            bits.next().unwrap(),
+            &next_bits()[..],
         )?;
 
         // handle remaining windows one-by-one
@@ -120,9 +168,7 @@ impl Gadget {
                 cs,
                 witness.map(|w| &w.windows[i]),
                 additions[next_j].1,
-                bits.next().unwrap(),
-                bits.next().unwrap(),
-                bits.next().unwrap(),
+                &next_bits()[..],
             )?;
 
             // update current result
@@ -158,6 +204,124 @@ mod tests {
         assert_eq!(point, real);
     }
 
+    #[test]
+    fn test_fixexp_window_sizes() {
+        let mut rng = thread_rng();
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(2048, 1);
+        let decomp = bits::Gadget::new_size(curve::FP_INNER_BITS);
+
+        for size in window::MIN_WINDOW_SIZE..=window::MAX_WINDOW_SIZE {
+            let fixexp = Gadget::new_window(curve::g0(), size);
+            let scalar = curve::Fp::random(&mut rng);
+            let real = scalar * curve::g0();
+            assert_eq!(fixexp.compute(scalar), real);
+
+            // prove
+
+            let mut prover = Prover::new(&pc_gens, Transcript::new(b"Test"));
+            let (witness, point) = fixexp.witness(scalar);
+            assert_eq!(point, real);
+            let bits = decomp.gadget_inner(&mut prover, Some(scalar)).unwrap();
+            let output = fixexp.gadget(&mut prover, &bits, Some(&witness)).unwrap();
+            output.constant(&mut prover, &real).unwrap();
+            let proof = prover.prove(&bp_gens).unwrap();
+
+            // verify
+
+            let mut verifier = Verifier::new(Transcript::new(b"Test"));
+            let bits = decomp.gadget_inner(&mut verifier, None).unwrap();
+            let output = fixexp.gadget(&mut verifier, &bits, None).unwrap();
+            output.constant(&mut verifier, &real).unwrap();
+            assert!(verifier.verify(&proof, &pc_gens, &bp_gens).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_window_counts() {
+        let counts = Gadget::window_counts(curve::g0());
+        assert_eq!(
+            counts.len(),
+            window::MAX_WINDOW_SIZE - window::MIN_WINDOW_SIZE + 1
+        );
+
+        // the default window size minimizes the multipliers
+        let fixexp = Gadget::new(curve::g0());
+        let best = fixexp.release_multipliers();
+        for (size, count) in counts.iter() {
+            let other = Gadget::new_window(curve::g0(), *size).release_multipliers();
+            assert!(other <= count.multipliers);
+            assert!(best <= other);
+            if *size < fixexp.window_size() {
+                assert!(best < other);
+            }
+        }
+    }
+
+    #[test]
+    fn test_malicious() {
+        let mut rng = thread_rng();
+        let fixexp = Gadget::new(curve::g0());
+        let decomp = bits::Gadget::new_size(curve::FP_INNER_BITS);
+
+        let scalar = curve::Fp::random(&mut rng);
+        let other = scalar + curve::Fp::from(1u32);
+
+        // a single wrong window lookup (the right term of additions[4]),
+        // the following additions are consistent with it: only the lookup constraint is violated
+        let forge = |witness: &mut Witness| {
+            let size = fixexp.window_size();
+            let mut bits = witness.windows[5].bits().to_vec();
+            bits[size - 1] = !bits[size - 1];
+            let lookup = fixexp.windows[5].lookup(&bits[..]);
+            for j in 4..witness.additions.len() {
+                let acc = witness.additions[j - 1].output();
+                let term = if j == 4 {
+                    lookup
+                } else {
+                    witness.windows[j + 1].lookup()
+                };
+                witness.additions[j] = addition::Gadget::witness(acc, term);
+            }
+        };
+        let forged = {
+            let (mut witness, _) = fixexp.witness(scalar);
+            forge(&mut witness);
+            witness.additions.last().unwrap().output()
+        };
+
+        // claimed results
+        let claims = [
+            scalar * curve::g0(),
+            other * curve::g0(),
+            other * curve::g0(),
+            forged,
+        ];
+
+        malicious::check_soundness(
+            claims.len(),
+            |i, prover| {
+                let (mut witness, _) = fixexp.witness(scalar);
+                match i {
+                    0 => (),
+                    1 => (), // wrong tag: claims the result for another scalar
+                    2 => witness = fixexp.witness(other).0, // lookups do not match the bits
+                    _ => forge(&mut witness), // wrong window lookup, consistent result
+                };
+                let bits = decomp.gadget_inner(prover, Some(scalar))?;
+                let out = fixexp.gadget(prover, &bits, Some(&witness))?;
+                out.constant(prover, &claims[i])?;
+                Ok(vec![])
+            },
+            |i, verifier, _| {
+                let bits = decomp.gadget_inner(verifier, None)?;
+                let out = fixexp.gadget(verifier, &bits, None)?;
+                out.constant(verifier, &claims[i])?;
+                Ok(())
+            },
+        );
+    }
+
     /*
     #[test]
     fn test_lookup_proof() {
@@ -0,0 +1,64 @@
+//! Harness for testing gadgets against a malicious prover:
+//! the prover is free to assign the wires arbitrarily (bypassing the honest witness generation)
+//! and the resulting proof must be rejected by the verifier.
+
+use bulletproofs::r1cs::*;
+use bulletproofs::{BulletproofGens, PedersenGens};
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use merlin::Transcript;
+
+const TRANSCRIPT_SEP: &'static [u8] = b"Malicious";
+
+// enough for a single fixed-base exponentiation (with debug constraints)
+const GENS_CAPACITY: usize = 4096;
+
+/// Runs the prover (which returns the commitments to its inputs),
+/// then the verifier on the commitments and returns whether the proof is accepted.
+pub fn prove_verify<P, V>(prove: P, verify: V) -> bool
+where
+    P: FnOnce(&mut Prover<'_, Transcript>) -> Result<Vec<CompressedRistretto>, R1CSError>,
+    V: FnOnce(&mut Verifier<Transcript>, &[CompressedRistretto]) -> Result<(), R1CSError>,
+{
+    let pc_gens = PedersenGens::default();
+    let bp_gens = BulletproofGens::new(GENS_CAPACITY, 1);
+
+    // prove
+    let mut prover = Prover::new(&pc_gens, Transcript::new(TRANSCRIPT_SEP));
+    let comms = match prove(&mut prover) {
+        Ok(comms) => comms,
+        Err(_) => return false,
+    };
+    let proof = match prover.prove(&bp_gens) {
+        Ok(proof) => proof,
+        Err(_) => return false,
+    };
+
+    // verify
+    let mut verifier = Verifier::new(Transcript::new(TRANSCRIPT_SEP));
+    if verify(&mut verifier, &comms[..]).is_err() {
+        return false;
+    }
+    verifier.verify(&proof, &pc_gens, &bp_gens).is_ok()
+}
+
+/// Asserts that the honest prover is accepted and every malicious prover is rejected.
+/// The provers (and the statements they claim) are indexed by `i`:
+/// `i = 0` is honest, `1 <= i < n` are malicious (i.e. claim false statements or use wrong assignments).
+pub fn check_soundness<P, V>(n: usize, prove: P, verify: V)
+where
+    P: Fn(usize, &mut Prover<'_, Transcript>) -> Result<Vec<CompressedRistretto>, R1CSError>,
+    V: Fn(usize, &mut Verifier<Transcript>, &[CompressedRistretto]) -> Result<(), R1CSError>,
+{
+    assert!(
+        prove_verify(|p| prove(0, p), |v, c| verify(0, v, c)),
+        "honest prover rejected"
+    );
+    for i in 1..n {
+        assert!(
+            !prove_verify(|p| prove(i, p), |v, c| verify(i, v, c)),
+            "malicious prover {} accepted",
+            i
+        );
+    }
+}
@@ -1,5 +1,9 @@
 // constraints for addition on the curve
-mod addition;
+pub mod addition;
+
+// generic twisted Edwards curves
+#[macro_use]
+pub mod edwards;
 
 // specification of the curve
 pub mod curve;
@@ -13,15 +17,26 @@ mod misc;
 // fixed based exponentiation
 pub mod fixexp;
 
+// variable based exponentiation
+pub mod varexp;
+
 // bit decomposition
 pub mod bits;
 
 // check permissible point
 pub mod permissible;
 
+// constraint counting
+pub mod count;
+
+// malicious prover harness
+#[cfg(test)]
+mod malicious;
+
 use misc::*;
 
 use curve::CurvePoint;
+use edwards::{EdwardsParams, Point};
 
 impl Into<Scalar> for curve::Fp {
     fn into(self: curve::Fp) -> Scalar {
@@ -52,7 +67,10 @@ impl CurveVariable {
     }
 
     // create a free variable and assign it the witness of "value"
-    fn assign<CS: ConstraintSystem>(cs: &mut CS, value: CurvePoint) -> Result<Self, R1CSError> {
+    fn assign<P: EdwardsParams, CS: ConstraintSystem>(
+        cs: &mut CS,
+        value: Point<P>,
+    ) -> Result<Self, R1CSError> {
         let x = cs.allocate(Some(value.x))?;
         let y = cs.allocate(Some(value.y))?;
         Ok(Self { x, y })
@@ -68,10 +86,10 @@ impl CurveVariable {
         Ok(())
     }
 
-    pub fn constant<CS: ConstraintSystem>(
+    pub fn constant<P: EdwardsParams, CS: ConstraintSystem>(
         &self,
         cs: &mut CS,
-        other: &CurvePoint,
+        other: &Point<P>,
     ) -> Result<(), R1CSError> {
         cs.constrain(self.x - other.x);
         cs.constrain(self.y - other.y);
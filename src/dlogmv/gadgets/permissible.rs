@@ -4,10 +4,11 @@ use curve25519_dalek::scalar::Scalar;
 use rug::{integer, Integer};
 
 use std::iter::FromIterator;
+use std::marker::PhantomData;
 
 use super::*;
 
-impl CurvePoint {
+impl<P: EdwardsParams> Point<P> {
     pub fn is_permissible(&self) -> bool {
         assert!(self.on_curve());
 
@@ -28,9 +29,10 @@ pub const SIZE_X_BITS: usize = 250;
 // the entire field
 pub const SIZE_Y_BITS: usize = 253;
 
-pub struct Gadget {
+pub struct Gadget<P: EdwardsParams = curve::Jabberwock> {
     x_bits: bits::Gadget,
     y_bits: bits::Gadget,
+    curve: PhantomData<P>,
 }
 
 fn is_prime(x: &Scalar) -> bool {
@@ -38,17 +40,21 @@ fn is_prime(x: &Scalar) -> bool {
     p.is_probably_prime(64) != integer::IsPrime::No
 }
 
-impl Gadget {
+impl<P: EdwardsParams> Gadget<P> {
     pub fn new() -> Self {
         let y_bits = bits::Gadget::new_size(250);
         let x_bits = bits::Gadget::new_even_size(253);
-        Self { x_bits, y_bits }
+        Self {
+            x_bits,
+            y_bits,
+            curve: PhantomData,
+        }
     }
 
     pub fn gadget<CS: ConstraintSystem>(
         &self,
         cs: &mut CS,
-        witness: Option<CurvePoint>,
+        witness: Option<Point<P>>,
     ) -> Result<CurveVariable, R1CSError> {
         #[cfg(test)]
         {
@@ -56,7 +62,15 @@ impl Gadget {
                 assert!(point.is_permissible())
             }
         }
+        self.constrain(cs, witness)
+    }
 
+    // the circuit itself: does not check the witness (which may be malicious in tests)
+    fn constrain<CS: ConstraintSystem>(
+        &self,
+        cs: &mut CS,
+        witness: Option<Point<P>>,
+    ) -> Result<CurveVariable, R1CSError> {
         // compute x as a linear combination
         let x_decomp = self.x_bits.gadget_scalar(cs, witness.map(|p| p.x))?;
         let (x1, x2, xx) = cs.allocate_multiplier(witness.map(|p| (p.x, p.x)))?;
@@ -71,12 +85,79 @@ impl Gadget {
         cs.constrain(y1 - y_lin);
         cs.constrain(LinearCombination::from(y1) - y2);
 
-        /*
         // check that x^2 + y^2 = 1 + d x^2 y^2
         let (_, _, xxyy) = cs.multiply(xx.into(), yy.into());
-        cs.constrain((xx + yy) - one() - curve::param_d() * xxyy);
-        */
+        cs.constrain((xx + yy) - one() - P::param_d() * xxyy);
 
         Ok(CurveVariable { x: x1, y: y1 })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use num_traits::Zero;
+
+    // prove that the point is permissible, without checking the witness
+    fn prove_verify(point: CurvePoint) -> bool {
+        let gadget = Gadget::new();
+        malicious::prove_verify(
+            |prover| {
+                let var = gadget.constrain(prover, Some(point))?;
+                var.constant(prover, &point)?;
+                Ok(vec![])
+            },
+            |verifier, _| {
+                let var = gadget.constrain(verifier, None)?;
+                var.constant(verifier, &point)
+            },
+        )
+    }
+
+    // find a permissible point
+    fn permissible() -> CurvePoint {
+        let mut point = curve::g0();
+        while !point.is_permissible() {
+            point = point + curve::g0();
+        }
+        point
+    }
+
+    #[test]
+    fn test_permissible() {
+        assert!(prove_verify(permissible()));
+    }
+
+    #[test]
+    fn test_identity() {
+        // the identity (0, 1) satisfies the range checks and the curve equation
+        let id: CurvePoint = Zero::zero();
+        assert!(id.on_curve());
+        assert!(prove_verify(id));
+    }
+
+    #[test]
+    fn test_off_curve() {
+        // satisfies the range checks, but not the curve equation
+        let point = permissible();
+        let off = CurvePoint::new(point.x + Scalar::from(2u32), point.y);
+        assert!(!off.on_curve());
+        assert!(!prove_verify(off));
+
+        let off = CurvePoint::new(Scalar::from(2u32), Scalar::from(3u32));
+        assert!(!off.on_curve());
+        assert!(!prove_verify(off));
+    }
+
+    #[test]
+    fn test_y_out_of_range() {
+        // on the curve, but y does not fit in 250 bits
+        let mut point = curve::g0();
+        while point.y.as_bytes()[31] < 4 || point.x.as_bytes()[0] & 1 != 0 {
+            point = point + curve::g0();
+        }
+        assert!(point.on_curve());
+        assert!(!prove_verify(point));
+    }
+}
@@ -0,0 +1,220 @@
+use super::*;
+
+use std::marker::PhantomData;
+
+use num_traits::Zero;
+
+pub struct Witness<P: EdwardsParams = curve::Jabberwock> {
+    doublings: Vec<addition::Witness<P>>,
+    additions: Vec<addition::Witness<P>>,
+}
+
+/// Scalar multiplication of a variable (e.g. committed) base point,
+/// the base point is not checked to be on the curve: this is up to the caller.
+pub struct Gadget<P: EdwardsParams = curve::Jabberwock> {
+    bits: usize,
+    curve: PhantomData<P>,
+}
+
+/// Number of multipliers used by a variable-base scalar multiplication with `bits`-bit scalars:
+/// a doubling and an addition for every bit but the first, plus two for every selection.
+pub fn multipliers(bits: usize) -> usize {
+    2 * (bits - 1) * addition::MULTIPLIERS + 2 * bits
+}
+
+// s ? p : identity
+fn select_value<P: EdwardsParams>(s: bool, p: Point<P>) -> Point<P> {
+    if s {
+        p
+    } else {
+        Zero::zero()
+    }
+}
+
+// constrain target = s ? p : identity = (s * p.x, 1 + s * (p.y - 1))
+fn select<CS: ConstraintSystem>(
+    cs: &mut CS,
+    s: bits::BitVariable,
+    p: CurveVariable,
+    target: CurveVariable,
+) -> Result<(), R1CSError> {
+    match s {
+        bits::BitVariable::Zero => {
+            cs.constrain(target.x.into());
+            cs.constrain(target.y - one());
+        }
+        bits::BitVariable::One => target.equal(cs, &p)?,
+        bits::BitVariable::Var(_) => {
+            let (_, _, x) = cs.multiply(s.into(), p.x.into());
+            let (_, _, y) = cs.multiply(s.into(), p.y - one());
+            cs.constrain(target.x - x);
+            cs.constrain(target.y - (one() + y));
+        }
+    }
+    Ok(())
+}
+
+impl<P: EdwardsParams> Gadget<P> {
+    /// Scalar multiplication with scalars of (at most) `bits` bits
+    pub fn new(bits: usize) -> Self {
+        assert!(bits >= 2);
+        Self {
+            bits,
+            curve: PhantomData,
+        }
+    }
+
+    pub fn compute(&self, base: Point<P>, scalar: P::Fp) -> Point<P> {
+        self.witness(base, scalar).1
+    }
+
+    pub fn witness(&self, base: Point<P>, scalar: P::Fp) -> (Witness<P>, Point<P>) {
+        let mut bits = P::fp_bits(&scalar).into_iter();
+        let mut next_bit = || bits.next().unwrap_or(false);
+
+        let mut doublings = Vec::with_capacity(self.bits - 1);
+        let mut additions = Vec::with_capacity(self.bits - 1);
+
+        let mut pow = base;
+        let mut acc = select_value(next_bit(), base);
+        for _ in 1..self.bits {
+            // pow = 2^i * base
+            let double = addition::Gadget::witness(pow, pow);
+            pow = double.output();
+            doublings.push(double);
+
+            // acc = acc + s_i * pow
+            let add = addition::Gadget::witness(acc, select_value(next_bit(), pow));
+            acc = add.output();
+            additions.push(add);
+        }
+        debug_assert!(!bits.any(|b| b), "scalar does not fit");
+        (
+            Witness {
+                doublings,
+                additions,
+            },
+            acc,
+        )
+    }
+
+    /// The bit decomposition is padded with zeroes (at "circuit compile time") in case it is to short.
+    pub fn gadget<CS: ConstraintSystem>(
+        &self,
+        cs: &mut CS,
+        base: CurveVariable,      // base point
+        scalar: &bits::Decompose, // bit decomposition of scalar
+        witness: Option<&Witness<P>>,
+    ) -> Result<CurveVariable, R1CSError> {
+        // traverse bits, least significant to most
+        let mut bits = scalar.iter_bits();
+        let s0 = bits.next().unwrap();
+
+        let mut pow = base;
+        let mut acc: Option<CurveVariable> = None;
+        for i in 0..self.bits - 1 {
+            // pow = 2^(i+1) * base
+            let (d1, d2, double) =
+                addition::Gadget::<P>::gadget(cs, witness.map(|w| &w.doublings[i]))?;
+            d1.equal(cs, &pow)?;
+            d2.equal(cs, &pow)?;
+
+            // acc = acc + s_(i+1) * pow
+            let (a1, a2, add) =
+                addition::Gadget::<P>::gadget(cs, witness.map(|w| &w.additions[i]))?;
+            match acc {
+                None => select(cs, s0, base, a1)?,
+                Some(acc) => a1.equal(cs, &acc)?,
+            }
+            select(cs, bits.next().unwrap(), double, a2)?;
+
+            pow = double;
+            acc = Some(add);
+        }
+
+        Ok(acc.unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    #[test]
+    fn test_varexp_compute() {
+        let mut rng = thread_rng();
+        let varexp = Gadget::new(curve::FP_INNER_BITS);
+        let base = curve::Fp::random(&mut rng) * curve::g0();
+        let scalar = curve::Fp::random(&mut rng);
+        assert_eq!(varexp.compute(base, scalar), scalar * base);
+        assert_eq!(varexp.compute(Zero::zero(), scalar), Zero::zero());
+    }
+
+    #[test]
+    fn test_varexp_proof() {
+        let mut rng = thread_rng();
+        let varexp = Gadget::new(curve::FP_INNER_BITS);
+        let decomp = bits::Gadget::new_size(curve::FP_INNER_BITS);
+
+        let base = curve::Fp::random(&mut rng) * curve::g1();
+        let other = curve::Fp::random(&mut rng) * curve::g1();
+        let scalar = curve::Fp::random(&mut rng);
+
+        // claimed (base, result)
+        let claims = [
+            (base, scalar * base),
+            (base, scalar * other),       // wrong result
+            (other, scalar * base),       // wrong base
+            (base, scalar * base + base), // off by one
+        ];
+
+        malicious::check_soundness(
+            claims.len(),
+            |i, prover| {
+                let (witness, _) = varexp.witness(base, scalar);
+                let (comm_x, x) = prover.commit(base.x, Scalar::random(&mut thread_rng()));
+                let (comm_y, y) = prover.commit(base.y, Scalar::random(&mut thread_rng()));
+                let bits = decomp.gadget_inner(prover, Some(scalar))?;
+                let out = varexp.gadget(prover, CurveVariable { x, y }, &bits, Some(&witness))?;
+                out.constant(prover, &claims[i].1)?;
+                Ok(vec![comm_x, comm_y])
+            },
+            |i, verifier, comms| {
+                let x = verifier.commit(comms[0]);
+                let y = verifier.commit(comms[1]);
+                let base = CurveVariable { x, y };
+                base.constant(verifier, &claims[i].0)?;
+                let bits = decomp.gadget_inner(verifier, None)?;
+                let out = varexp.gadget(verifier, base, &bits, None)?;
+                out.constant(verifier, &claims[i].1)?;
+                Ok(())
+            },
+        );
+    }
+
+    #[test]
+    fn test_varexp_count() {
+        let mut cs = count::Counter::new();
+        let add = cs
+            .measure(|cs| addition::Gadget::<curve::Jabberwock>::gadget(cs, None).map(|_| ()))
+            .unwrap();
+
+        for n in [2, 8, curve::FP_INNER_BITS].iter().copied() {
+            let varexp = Gadget::<curve::Jabberwock>::new(n);
+            let scalar = bits::Gadget::new_size(n).gadget(&mut cs, None).unwrap();
+            let base = CurveVariable::free(&mut cs).unwrap();
+            let count = cs
+                .measure(|cs| varexp.gadget(cs, base, &scalar, None).map(|_| ()))
+                .unwrap();
+            println!("varexp ({} bits): {:?}", n, count);
+            assert_eq!(count.multipliers, 2 * (n - 1) * add.multipliers + 2 * n);
+            if add.multipliers == addition::MULTIPLIERS {
+                assert_eq!(count.multipliers, multipliers(n));
+            }
+        }
+    }
+}
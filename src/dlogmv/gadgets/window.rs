@@ -4,133 +4,177 @@ use num_traits::Zero;
 
 use curve25519_dalek::scalar::Scalar;
 
+use std::marker::PhantomData;
+
 use super::*;
 
-pub const WINDOW_SIZE: usize = 3;
-pub const WINDOW_ELEMS: usize = 1 << WINDOW_SIZE;
+/// Supported window sizes (in bits)
+pub const MIN_WINDOW_SIZE: usize = 2;
+pub const MAX_WINDOW_SIZE: usize = 6;
 
-pub struct Witness {
-    lookup: CurvePoint,
-    b0: bool,
-    b1: bool,
-    b2: bool,
+pub struct Witness<P: EdwardsParams = curve::Jabberwock> {
+    lookup: Point<P>,
+    bits: Vec<bool>,
 }
 
-impl Witness {
-    pub fn lookup(&self) -> CurvePoint {
+impl<P: EdwardsParams> Witness<P> {
+    pub fn lookup(&self) -> Point<P> {
         self.lookup
     }
 
-    pub fn bits(&self) -> (bool, bool, bool) {
-        (self.b0, self.b1, self.b2)
+    pub fn bits(&self) -> &[bool] {
+        &self.bits[..]
+    }
+}
+
+pub struct EdwardsWindow<P: EdwardsParams = curve::Jabberwock> {
+    size: usize,
+    u: Vec<Scalar>,
+    v: Vec<Scalar>,
+    curve: PhantomData<P>,
+}
+
+/// Number of multipliers used by a single lookup in a window of `size` bits:
+/// one for every product of two or more of the high bits (shared between coordinates),
+/// plus one for each coordinate.
+pub fn multipliers(size: usize) -> usize {
+    (1 << (size - 1)) - size + 2
+}
+
+// Compute the coefficients of the multilinear polynomial interpolating the table,
+// i.e. t[j] = sum_{i subset of j} c[i]
+fn interpolate(t: &[Scalar]) -> Vec<Scalar> {
+    let mut c = t.to_vec();
+    let mut bit = 1;
+    while bit < c.len() {
+        for j in 0..c.len() {
+            if j & bit != 0 {
+                c[j] = c[j] - c[j ^ bit];
+            }
+        }
+        bit <<= 1;
     }
+    c
 }
 
-pub struct EdwardsWindow {
-    u: [Scalar; WINDOW_ELEMS],
-    v: [Scalar; WINDOW_ELEMS],
+// Compute all products of subsets of the bits: m[j] = prod_{i in j} s[i]
+fn monomials<CS: ConstraintSystem>(cs: &mut CS, s: &[bits::BitVariable]) -> Vec<bits::BitVariable> {
+    let mut m = Vec::with_capacity(1 << s.len());
+    m.push(bits::BitVariable::one());
+    for j in 1..(1 << s.len()) {
+        let low = j & j.wrapping_neg();
+        let rest = j ^ low;
+        let bit = s[low.trailing_zeros() as usize];
+        m.push(if rest == 0 {
+            bit
+        } else {
+            bits::BitVariable::mul(cs, m[rest], bit)
+        });
+    }
+    m
 }
 
-// constrain u = u[s0 + s1*2 + s2*4] with sa = s1 * s2
+// constrain e = t[s0 + 2*s1 + 4*s2 + ...] given the monomials m over the high bits (s1, s2, ...):
+//
+// e = A(s1, ...) + s0 * (B(s1, ...) - A(s1, ...))
+//
+// where A and B interpolate the even and odd entries of the table respectively.
 #[inline(always)]
 fn lookup_gadget<CS: ConstraintSystem>(
     cs: &mut CS,
-    sa: bits::BitVariable,
+    m: &[bits::BitVariable],
     s0: bits::BitVariable,
-    s1: bits::BitVariable,
-    s2: bits::BitVariable,
     e: LinearCombination,
-    u: &[Scalar; 8],
+    t: &[Scalar],
 ) {
+    debug_assert_eq!(t.len(), 2 * m.len());
+
+    let even: Vec<Scalar> = t.iter().step_by(2).copied().collect();
+    let odd: Vec<Scalar> = t.iter().skip(1).step_by(2).copied().collect();
+    let a = interpolate(&even[..]);
+    let b = interpolate(&odd[..]);
+
     // left side
-    let (_, _, left): (Variable, Variable, Variable) = cs.multiply(s0.into(), {
-        let f = -(u[0] * sa) + (u[0] * s2) + (u[0] * s1) - u[0] + (u[2] * sa);
-        let f = f - (u[2] * s1) + (u[4] * sa) - (u[4] * s2) - (u[6] * sa);
-        let f = f + (u[1] * sa) - (u[1] * s2) - (u[1] * s1) + u[1] - (u[3] * sa);
-        let f = f + (u[3] * s1) - (u[5] * sa) + (u[5] * s2) + (u[7] * sa);
-        f
-    });
-
-    // right size
-    let right: LinearCombination = e - (u[0] * sa) + (u[0] * s2) + (u[0] * s1) - u[0] + (u[2] * sa);
-    let right = right - (u[2] * s1) + (u[4] * sa) - (u[4] * s2) - (u[6] * sa);
+    let mut f: LinearCombination = Scalar::zero().into();
+    for j in 0..m.len() {
+        f = f + m[j] * (b[j] - a[j]);
+    }
+    let (_, _, left): (Variable, Variable, Variable) = cs.multiply(s0.into(), f);
+
+    // right side
+    let mut right: LinearCombination = e;
+    for j in 0..m.len() {
+        right = right - m[j] * a[j];
+    }
 
     // left == right
     cs.constrain(left - right)
 }
 
-impl EdwardsWindow {
-    /// Creates a new 3-bit lookup table
-    pub fn new(d: Scalar, p1: CurvePoint) -> Self {
-        let p0: CurvePoint = Zero::zero();
-        let p2 = p1 + p1;
-        let p3 = p2 + p1;
-        let p4 = p3 + p1;
-        let p5 = p4 + p1;
-        let p6 = p5 + p1;
-        let p7 = p6 + p1;
+impl<P: EdwardsParams> EdwardsWindow<P> {
+    /// Creates a new lookup table with 2^size entries: [0] p1, [1] p1, ..., [2^size - 1] p1
+    pub fn new(d: Scalar, p1: Point<P>, size: usize) -> Self {
+        debug_assert!(size >= MIN_WINDOW_SIZE && size <= MAX_WINDOW_SIZE);
+        let mut u = Vec::with_capacity(1 << size);
+        let mut v = Vec::with_capacity(1 << size);
+        let mut p: Point<P> = Zero::zero();
+        for _ in 0..(1 << size) {
+            u.push(p.x);
+            v.push(p.y);
+            p = p + p1;
+        }
         EdwardsWindow {
-            u: [p0.x, p1.x, p2.x, p3.x, p4.x, p5.x, p6.x, p7.x],
-            v: [p0.y, p1.y, p2.y, p3.y, p4.y, p5.y, p6.y, p7.y],
+            size,
+            u,
+            v,
+            curve: PhantomData,
         }
     }
 
-    pub fn lookup(
-        &self,
-        b0: bool, // scalar (0th bit)
-        b1: bool, // scalar (1st bit)
-        b2: bool, // scalar (2nd bit)
-    ) -> CurvePoint {
-        let i0: usize = b0 as usize;
-        let i1: usize = b1 as usize;
-        let i2: usize = b2 as usize;
-        let i = i0 + i1 * 2 + i2 * 4;
-        CurvePoint {
-            x: self.u[i],
-            y: self.v[i],
-        }
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Bits are little-endian
+    pub fn lookup(&self, bits: &[bool]) -> Point<P> {
+        debug_assert_eq!(bits.len(), self.size);
+        let i = bits.iter().rev().fold(0, |i, b| (i << 1) | (*b as usize));
+        Point::new(self.u[i], self.v[i])
     }
 
     /// Compute assignments to intermediate wires.
     /// Computes:
     ///
     /// - uv   (lookup result)
-    /// - xy_r (resulting point)
     ///
     /// From:
     ///
-    /// - xy (input point)
-    /// - s0, s1, s2 (bit decomposition of 3-bit scalar)
-    pub fn witness(
-        &self,
-        b0: bool, // scalar (0th bit)
-        b1: bool, // scalar (1st bit)
-        b2: bool, // scalar (2nd bit)
-    ) -> Witness {
+    /// - bits (little-endian bit decomposition of the window)
+    pub fn witness(&self, bits: &[bool]) -> Witness<P> {
         Witness {
-            lookup: self.lookup(b0, b1, b2),
-            b0,
-            b1,
-            b2,
+            lookup: self.lookup(bits),
+            bits: bits.to_vec(),
         }
     }
 
     /// Checks that:
     ///
-    /// - uv = window[s0 + 2*s1 + 4*s2]
-    /// - xy_r = xy <Edwards Addition> uv
+    /// - uv = window[s0 + 2*s1 + 4*s2 + ...]
     pub fn gadget<CS: ConstraintSystem>(
         &self,
         cs: &mut CS,
-        witness: Option<&Witness>,
+        witness: Option<&Witness<P>>,
         lookup: CurveVariable,
-        s0: bits::BitVariable,
-        s1: bits::BitVariable,
-        s2: bits::BitVariable, // s = s0 + 2 * s1 + 4 * s2
+        s: &[bits::BitVariable], // s = s0 + 2 * s1 + 4 * s2 + ...
     ) -> Result<(), R1CSError> {
-        let sa = bits::BitVariable::mul(cs, s1, s2);
-        lookup_gadget(cs, sa, s0, s1, s2, lookup.x.into(), &self.u);
-        lookup_gadget(cs, sa, s0, s1, s2, lookup.y.into(), &self.v);
+        if s.len() != self.size {
+            return Err(R1CSError::GadgetError {
+                description: "window size does not match number of bits".to_string(),
+            });
+        }
+        let m = monomials(cs, &s[1..]);
+        lookup_gadget(cs, &m[..], s[0], lookup.x.into(), &self.u[..]);
+        lookup_gadget(cs, &m[..], s[0], lookup.y.into(), &self.v[..]);
         Ok(())
     }
 }
@@ -139,132 +183,77 @@ mod tests {
     use super::*;
 
     use bulletproofs::{BulletproofGens, PedersenGens};
-    use curve25519_dalek::ristretto::CompressedRistretto;
     use merlin::Transcript;
     use rand::{thread_rng, Rng};
-    use test::Bencher;
 
-    #[test]
-    fn test_lookup_proof() {
+    fn test_lookup_proof(size: usize) {
         let pc_gens = PedersenGens::default();
         let bp_gens = BulletproofGens::new(128, 1);
 
         let mut rng = thread_rng();
 
-        let u: [Scalar; 8] = [
-            Scalar::random(&mut rng),
-            Scalar::random(&mut rng),
-            Scalar::random(&mut rng),
-            Scalar::random(&mut rng),
-            Scalar::random(&mut rng),
-            Scalar::random(&mut rng),
-            Scalar::random(&mut rng),
-            Scalar::random(&mut rng),
-        ];
-
-        let b0: bool = rng.gen();
-        let b1: bool = rng.gen();
-        let b2: bool = rng.gen();
-
-        let i = (b0 as usize) + 2 * (b1 as usize) + 4 * (b2 as usize);
-
-        // happy path
-        {
-            let mut prover = Prover::new(&pc_gens, Transcript::new(b"Test"));
-            let mut verifier = Verifier::new(Transcript::new(b"Test"));
-
-            let s0 = bits::BitVariable::new(&mut prover, b0).unwrap();
-            let s1 = bits::BitVariable::new(&mut prover, b1).unwrap();
-            let s2 = bits::BitVariable::new(&mut prover, b2).unwrap();
-            let sa = bits::BitVariable::mul(&mut prover, s1, s2);
-
-            let blind_e = Scalar::random(&mut rng);
-            let value_e = u[i];
-
-            // prove
-
-            let (comm_e, input_e) = prover.commit(value_e, blind_e);
+        let u: Vec<Scalar> = (0..(1 << size)).map(|_| Scalar::random(&mut rng)).collect();
 
-            lookup_gadget(&mut prover, sa, s0, s1, s2, input_e.into(), &u);
-
-            let proof = prover.prove(&bp_gens).unwrap();
-
-            // verify
+        let b: Vec<bool> = (0..size).map(|_| rng.gen()).collect();
 
-            let s0 = bits::BitVariable::free(&mut verifier).unwrap();
-            let s1 = bits::BitVariable::free(&mut verifier).unwrap();
-            let s2 = bits::BitVariable::free(&mut verifier).unwrap();
-            let sa = bits::BitVariable::mul(&mut verifier, s1, s2);
+        let i = b.iter().rev().fold(0, |i, b| (i << 1) | (*b as usize));
 
-            let input_e = verifier.commit(comm_e);
-
-            lookup_gadget(&mut verifier, sa, s0, s1, s2, input_e.into(), &u);
-
-            assert!(verifier.verify(&proof, &pc_gens, &bp_gens).is_ok())
-        }
-
-        // error path
-        {
+        for (value_e, valid) in vec![(u[i], true), (Scalar::random(&mut rng), false)] {
             let mut prover = Prover::new(&pc_gens, Transcript::new(b"Test"));
             let mut verifier = Verifier::new(Transcript::new(b"Test"));
 
-            let s0 = bits::BitVariable::new(&mut prover, b0).unwrap();
-            let s1 = bits::BitVariable::new(&mut prover, b1).unwrap();
-            let s2 = bits::BitVariable::new(&mut prover, b2).unwrap();
-            let sa = bits::BitVariable::mul(&mut prover, s1, s2);
+            let s: Vec<bits::BitVariable> = b
+                .iter()
+                .map(|b| bits::BitVariable::new(&mut prover, *b).unwrap())
+                .collect();
+            let m = monomials(&mut prover, &s[1..]);
 
             let blind_e = Scalar::random(&mut rng);
-            let value_e = Scalar::random(&mut rng);
 
             // prove
 
             let (comm_e, input_e) = prover.commit(value_e, blind_e);
 
-            lookup_gadget(&mut prover, sa, s0, s1, s2, input_e.into(), &u);
+            lookup_gadget(&mut prover, &m[..], s[0], input_e.into(), &u[..]);
 
             let proof = prover.prove(&bp_gens).unwrap();
 
             // verify
 
-            let s0 = bits::BitVariable::free(&mut verifier).unwrap();
-            let s1 = bits::BitVariable::free(&mut verifier).unwrap();
-            let s2 = bits::BitVariable::free(&mut verifier).unwrap();
-            let sa = bits::BitVariable::mul(&mut verifier, s1, s2);
+            let s: Vec<bits::BitVariable> = (0..size)
+                .map(|_| bits::BitVariable::free(&mut verifier).unwrap())
+                .collect();
+            let m = monomials(&mut verifier, &s[1..]);
 
             let input_e = verifier.commit(comm_e);
 
-            lookup_gadget(&mut verifier, sa, s0, s1, s2, input_e.into(), &u);
+            lookup_gadget(&mut verifier, &m[..], s[0], input_e.into(), &u[..]);
 
-            assert!(verifier.verify(&proof, &pc_gens, &bp_gens).is_err())
+            assert_eq!(verifier.verify(&proof, &pc_gens, &bp_gens).is_ok(), valid);
         }
     }
 
-    #[test]
-    fn test_window_proof() {
-        let ed_window = EdwardsWindow::new(
-            Scalar::from_bytes_mod_order([
-                0x33, 0xd1, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9,
-                0xde, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x10,
-            ]),
-            curve::g0(),
-        );
+    fn test_window_proof(size: usize) {
+        let ed_window = EdwardsWindow::new(curve::param_d(), curve::g0(), size);
 
         let pc_gens = PedersenGens::default();
         let bp_gens = BulletproofGens::new(128, 1);
 
         // compute lookup
 
-        let witness = ed_window.witness(true, true, true);
+        let mut rng = thread_rng();
+        let b: Vec<bool> = (0..size).map(|_| rng.gen()).collect();
+        let witness = ed_window.witness(&b[..]);
 
         // prove
 
         let transcript = Transcript::new(b"Test");
         let mut prover = Prover::new(&pc_gens, transcript);
 
-        let s0 = bits::BitVariable::new(&mut prover, true).unwrap();
-        let s1 = bits::BitVariable::new(&mut prover, true).unwrap();
-        let s2 = bits::BitVariable::new(&mut prover, true).unwrap();
+        let s: Vec<bits::BitVariable> = b
+            .iter()
+            .map(|b| bits::BitVariable::new(&mut prover, *b).unwrap())
+            .collect();
 
         let blind_x = Scalar::from(53753735735u64);
         let blind_y = Scalar::from(46713612753u64);
@@ -278,7 +267,7 @@ mod tests {
         };
 
         ed_window
-            .gadget(&mut prover, Some(&witness), lookup, s0, s1, s2)
+            .gadget(&mut prover, Some(&witness), lookup, &s[..])
             .unwrap();
 
         let proof = prover.prove(&bp_gens).unwrap();
@@ -288,9 +277,9 @@ mod tests {
         let transcript = Transcript::new(b"Test");
         let mut verifier = Verifier::new(transcript);
 
-        let s0 = bits::BitVariable::free(&mut verifier).unwrap();
-        let s1 = bits::BitVariable::free(&mut verifier).unwrap();
-        let s2 = bits::BitVariable::free(&mut verifier).unwrap();
+        let s: Vec<bits::BitVariable> = (0..size)
+            .map(|_| bits::BitVariable::free(&mut verifier).unwrap())
+            .collect();
 
         let lookup_x = verifier.commit(comm_x);
         let lookup_y = verifier.commit(comm_y);
@@ -301,9 +290,96 @@ mod tests {
         };
 
         ed_window
-            .gadget(&mut verifier, None, lookup, s0, s1, s2)
+            .gadget(&mut verifier, None, lookup, &s[..])
             .unwrap();
 
         verifier.verify(&proof, &pc_gens, &bp_gens).unwrap()
     }
+
+    #[test]
+    fn test_interpolate() {
+        let mut rng = thread_rng();
+        let t: Vec<Scalar> = (0..32).map(|_| Scalar::random(&mut rng)).collect();
+        let c = interpolate(&t[..]);
+        for j in 0..t.len() {
+            let mut e = Scalar::zero();
+            for i in 0..t.len() {
+                if i & j == i {
+                    e += c[i];
+                }
+            }
+            assert_eq!(e, t[j]);
+        }
+    }
+
+    #[test]
+    fn test_lookup_proofs() {
+        for size in MIN_WINDOW_SIZE..=MAX_WINDOW_SIZE {
+            test_lookup_proof(size);
+        }
+    }
+
+    #[test]
+    fn test_window_proofs() {
+        for size in MIN_WINDOW_SIZE..=MAX_WINDOW_SIZE {
+            test_window_proof(size);
+        }
+    }
+
+    #[test]
+    fn test_window_lookup() {
+        let mut rng = thread_rng();
+        for size in MIN_WINDOW_SIZE..=MAX_WINDOW_SIZE {
+            let ed_window = EdwardsWindow::new(curve::param_d(), curve::g0(), size);
+            let b: Vec<bool> = (0..size).map(|_| rng.gen()).collect();
+            let i = b.iter().rev().fold(0u32, |i, b| (i << 1) | (*b as u32));
+            assert_eq!(ed_window.lookup(&b[..]), curve::Fp::from(i) * curve::g0());
+        }
+    }
+
+    fn test_malicious_window(size: usize) {
+        let ed_window = EdwardsWindow::new(curve::param_d(), curve::g0(), size);
+
+        let mut rng = thread_rng();
+        let b: Vec<bool> = (0..size).map(|_| rng.gen()).collect();
+
+        malicious::check_soundness(
+            4,
+            |i, prover| {
+                let mut bits = b.clone();
+                let mut lookup = ed_window.lookup(&b[..]);
+                match i {
+                    0 => (),
+                    1 => bits[0] = !bits[0], // bits do not match the lookup
+                    2 => bits[size - 1] = !bits[size - 1],
+                    _ => lookup = lookup + curve::g0(), // point not in the table at the index
+                };
+                let s: Vec<bits::BitVariable> = bits
+                    .iter()
+                    .map(|b| bits::BitVariable::new(prover, *b))
+                    .collect::<Result<_, _>>()?;
+                let (comm_x, x) = prover.commit(lookup.x, Scalar::random(&mut thread_rng()));
+                let (comm_y, y) = prover.commit(lookup.y, Scalar::random(&mut thread_rng()));
+                ed_window.gadget(prover, None, CurveVariable { x, y }, &s[..])?;
+
+                Ok(vec![comm_x, comm_y])
+            },
+            |_, verifier, comms| {
+                let s: Vec<bits::BitVariable> = (0..size)
+                    .map(|_| bits::BitVariable::free(verifier))
+                    .collect::<Result<_, _>>()?;
+                let x = verifier.commit(comms[0]);
+                let y = verifier.commit(comms[1]);
+                ed_window.gadget(verifier, None, CurveVariable { x, y }, &s[..])?;
+                Ok(())
+            },
+        );
+    }
+
+    #[test]
+    fn test_malicious_windows() {
+        for size in MIN_WINDOW_SIZE..=MAX_WINDOW_SIZE {
+            test_malicious_window(size);
+        }
+    }
 }
@@ -5,11 +5,13 @@ use merlin::Transcript;
 use bulletproofs::r1cs::*;
 use bulletproofs::{BulletproofGens, PedersenGens};
 
-use gadgets::{bits, curve, fixexp, permissible, CurveVariable};
+use gadgets::{addition, bits, count, curve, fixexp, permissible, CurveVariable};
 
 use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
 use curve25519_dalek::scalar::Scalar;
 
+use rand_core::OsRng;
+
 use serde::{Deserialize, Serialize};
 
 use proofsize_derive::*;
@@ -19,11 +21,38 @@ const TRANSCRIPT_SEP: &'static [u8] = b"ZK_EXP";
 #[derive(Serialize, Deserialize, Debug, ProofSize)]
 pub struct Proof(R1CSProof);
 
+/// ElGamal encryption of the signers public key under the key of the opener
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Ciphertext {
+    pub c1: curve::CurvePoint, // r * g
+    pub c2: curve::CurvePoint, // pk + r * opener
+}
+
 struct Witness {
     pk: curve::CurvePoint, // public key
     sk: curve::Fp,         // private key
     g_exp: fixexp::Witness,
-    h_exp: fixexp::Witness,
+    h_exp: Option<fixexp::Witness>,
+    escrow: Option<EscrowWitness>,
+}
+
+struct EscrowWitness {
+    r: curve::Fp,           // encryption randomness
+    r_exp: fixexp::Witness, // r * g
+    o_exp: fixexp::Witness, // r * opener
+    add: addition::Witness, // pk + r * opener
+}
+
+// encryption of the public key to a designated opener
+struct Escrow {
+    // public key of the opener
+    opener: curve::CurvePoint,
+
+    // bit decomposition of encryption randomness
+    decomp: bits::Gadget,
+
+    // exponentiation of the opener key
+    o_exp: fixexp::Gadget,
 }
 
 pub struct Statement {
@@ -38,7 +67,10 @@ pub struct Statement {
 
     // proof of exp
     g_exp: fixexp::Gadget,
-    h_exp: fixexp::Gadget,
+    h_exp: Option<fixexp::Gadget>, // omitted in unlinkable mode
+
+    // accountable mode (if enabled)
+    escrow: Option<Escrow>,
 }
 
 impl Statement {
@@ -46,22 +78,84 @@ impl Statement {
         &self.pc_gens
     }
 
-    pub fn new(g: curve::CurvePoint, h: curve::CurvePoint) -> Self {
-        Self {
+    /// If `h` is None the tag (and the exponentiation of `h`) is omitted, i.e. signatures are unlinkable
+    pub fn new(g: curve::CurvePoint, h: Option<curve::CurvePoint>) -> Self {
+        Self::with_escrow(g, h, None)
+    }
+
+    /// Like `new`, but additionally proves that the public key is encrypted under `opener`
+    pub fn new_accountable(
+        g: curve::CurvePoint,
+        h: Option<curve::CurvePoint>,
+        opener: curve::CurvePoint,
+    ) -> Self {
+        Self::with_escrow(
+            g,
+            h,
+            Some(Escrow {
+                opener,
+                decomp: bits::Gadget::new_size(curve::FP_INNER_BITS),
+                o_exp: fixexp::Gadget::new(opener),
+            }),
+        )
+    }
+
+    fn with_escrow(
+        g: curve::CurvePoint,
+        h: Option<curve::CurvePoint>,
+        escrow: Option<Escrow>,
+    ) -> Self {
+        let mut statement = Self {
             pc_gens: PedersenGens::default(),
-            bp_gens: BulletproofGens::new(4100, 1),
+            bp_gens: BulletproofGens::new(0, 1),
             permissible: permissible::Gadget::new(),
-            decomp: bits::Gadget::new_size(250),
+            decomp: bits::Gadget::new_size(curve::FP_INNER_BITS),
             g_exp: fixexp::Gadget::new(g),
-            h_exp: fixexp::Gadget::new(h),
-        }
+            h_exp: h.map(fixexp::Gadget::new),
+            escrow,
+        };
+
+        // size the generators to the circuit
+        statement.bp_gens = BulletproofGens::new(statement.count().gens_capacity(), 1);
+        statement
+    }
+
+    /// Size of the circuit (number of multipliers, constraints and committed values)
+    pub fn count(&self) -> count::Count {
+        let mut cs = count::Counter::new();
+        let pk_y = cs.commit();
+
+        // placeholders for the public inputs
+        let tag = curve::g0();
+        let escrow = Ciphertext {
+            c1: curve::g0(),
+            c2: curve::g0(),
+        };
+
+        self.gadget(&mut cs, pk_y, Some(&tag), Some(&escrow), None)
+            .unwrap();
+        cs.count()
+    }
+
+    pub fn is_linkable(&self) -> bool {
+        self.h_exp.is_some()
+    }
+
+    pub fn is_accountable(&self) -> bool {
+        self.escrow.is_some()
+    }
+
+    /// Key of the opener (accountable mode)
+    pub fn opener(&self) -> Option<curve::CurvePoint> {
+        self.escrow.as_ref().map(|escrow| escrow.opener)
     }
 
     fn gadget<CS: ConstraintSystem>(
         &self,
         cs: &mut CS,
-        pk_y: Variable,                  // x coordinate of the public key
-        claimed_tag: &curve::CurvePoint, // tag provided with the signature
+        pk_y: Variable,                          // x coordinate of the public key
+        claimed_tag: Option<&curve::CurvePoint>, // tag provided with the signature
+        claimed_escrow: Option<&Ciphertext>,     // encryption of public key (accountable mode)
         witness: Option<&Witness>,
     ) -> Result<(), R1CSError> {
         // decompose secret key
@@ -76,20 +170,60 @@ impl Statement {
         let per_pk = self.permissible.gadget(cs, witness.map(|w| w.pk))?;
         per_pk.equal(cs, &exp_pk)?;
 
-        // recompute tag
-        let exp_tag = self.h_exp.gadget(cs, &decomp, witness.map(|w| &w.h_exp))?;
-        exp_tag.constant(cs, claimed_tag)?;
+        // recompute tag (linkable mode)
+        if let Some(h_exp) = &self.h_exp {
+            let claimed_tag = claimed_tag.ok_or(R1CSError::GadgetError {
+                description: "missing tag in linkable mode".to_string(),
+            })?;
+            let exp_tag = h_exp.gadget(cs, &decomp, witness.and_then(|w| w.h_exp.as_ref()))?;
+            exp_tag.constant(cs, claimed_tag)?;
+        }
+
+        // check that the ciphertext encrypts the same public key
+        if let Some(escrow) = &self.escrow {
+            let claimed = claimed_escrow.ok_or(R1CSError::GadgetError {
+                description: "missing ciphertext in accountable mode".to_string(),
+            })?;
+            let escrow_witness = witness.and_then(|w| w.escrow.as_ref());
+
+            // decompose encryption randomness
+            let r_decomp = escrow
+                .decomp
+                .gadget_inner(cs, escrow_witness.map(|w| w.r))?;
+
+            // c1 = r * g
+            let exp_c1 = self
+                .g_exp
+                .gadget(cs, &r_decomp, escrow_witness.map(|w| &w.r_exp))?;
+            exp_c1.constant(cs, &claimed.c1)?;
+
+            // c2 = pk + r * opener
+            let exp_o = escrow
+                .o_exp
+                .gadget(cs, &r_decomp, escrow_witness.map(|w| &w.o_exp))?;
+            let (in1, in2, out) = addition::Gadget::gadget(cs, escrow_witness.map(|w| &w.add))?;
+            in1.equal(cs, &exp_pk)?;
+            in2.equal(cs, &exp_o)?;
+            out.constant(cs, &claimed.c2)?;
+        }
 
         Ok(())
     }
 
     pub fn prove(
         &self,
+        msg: &[u8],            // message signed
         pk: curve::CurvePoint, // public key
         sk: curve::Fp,         // private key
         r: Scalar,             // randomness of commitment
-    ) -> (Proof, CompressedRistretto, curve::CurvePoint) {
-        let transcript = Transcript::new(TRANSCRIPT_SEP);
+    ) -> (
+        Proof,
+        CompressedRistretto,
+        Option<curve::CurvePoint>,
+        Option<Ciphertext>,
+    ) {
+        let mut transcript = Transcript::new(TRANSCRIPT_SEP);
+        transcript.append_message(b"msg", msg);
         let mut prover = Prover::new(&self.pc_gens, transcript);
 
         // commit to y-coordinate of public key
@@ -100,40 +234,82 @@ impl Statement {
         assert_eq!(pk_exp, pk);
 
         // compute tag
-        let (h_exp, tag) = self.h_exp.witness(sk);
+        let (h_exp, tag) = match &self.h_exp {
+            None => (None, None),
+            Some(h_exp) => {
+                let (witness, tag) = h_exp.witness(sk);
+                (Some(witness), Some(tag))
+            }
+        };
+
+        // encrypt public key to opener
+        let (escrow, ciphertext) = match &self.escrow {
+            None => (None, None),
+            Some(escrow) => {
+                let r = curve::Fp::random(&mut OsRng);
+                let (r_exp, c1) = self.g_exp.witness(r);
+                let (o_exp, ro) = escrow.o_exp.witness(r);
+                let add = addition::Gadget::witness(pk, ro);
+                let c2 = add.output();
+                (
+                    Some(EscrowWitness {
+                        r,
+                        r_exp,
+                        o_exp,
+                        add,
+                    }),
+                    Some(Ciphertext { c1, c2 }),
+                )
+            }
+        };
 
         // constrain entire relation
         self.gadget(
             &mut prover,
             pk_y,
-            &tag,
+            tag.as_ref(),
+            ciphertext.as_ref(),
             Some(&Witness {
                 pk,
                 sk,
                 g_exp,
                 h_exp,
+                escrow,
             }),
         )
         .unwrap();
 
-        // prove, return commitment to public and tag
+        // prove, return commitment to public, tag and ciphertext
         let proof = prover.prove(&self.bp_gens).unwrap();
-        (Proof(proof), comm_pk, tag)
+        (Proof(proof), comm_pk, tag, ciphertext)
     }
 
     pub fn verify(
         &self,
+        msg: &[u8], // message signed
         proof: &Proof,
         comm_pk: CompressedRistretto,
-        tag: &curve::CurvePoint,
+        tag: Option<&curve::CurvePoint>,
+        escrow: Option<&Ciphertext>,
     ) -> bool {
-        let transcript = Transcript::new(TRANSCRIPT_SEP);
+        // tag must be present iff in linkable mode
+        if self.is_linkable() != tag.is_some() {
+            return false;
+        }
+
+        // ciphertext must be present iff in accountable mode
+        if self.is_accountable() != escrow.is_some() {
+            return false;
+        }
+
+        let mut transcript = Transcript::new(TRANSCRIPT_SEP);
+        transcript.append_message(b"msg", msg);
         let mut verifier = Verifier::new(transcript);
 
         // input y-coordinate of public key
         let pk_y = verifier.commit(comm_pk);
 
-        self.gadget(&mut verifier, pk_y, tag, None).unwrap();
+        self.gadget(&mut verifier, pk_y, tag, escrow, None).unwrap();
 
         verifier
             .verify(&proof.0, &self.pc_gens, &self.bp_gens)
@@ -155,25 +331,123 @@ mod tests {
     fn test_prove_verify() {
         let sk = SigningKey::new();
 
-        let statement = Statement::new(curve::g0(), curve::g1());
+        let statement = Statement::new(curve::g0(), Some(curve::g1()));
 
         let r = Scalar::random(&mut OsRng);
 
-        let (proof, comm_pk, tag) = statement.prove(sk.pk, sk.sk, r);
+        let (proof, comm_pk, tag, escrow) = statement.prove(b"msg", sk.pk, sk.sk, r);
+
+        assert!(escrow.is_none());
+        assert!(statement.verify(b"msg", &proof, comm_pk, tag.as_ref(), None));
+        assert!(!statement.verify(b"other", &proof, comm_pk, tag.as_ref(), None));
+    }
 
-        assert!(statement.verify(&proof, comm_pk, &tag))
+    #[test]
+    fn test_wrong_tag() {
+        let sk = SigningKey::new();
+
+        let statement = Statement::new(curve::g0(), Some(curve::g1()));
+
+        let r = Scalar::random(&mut OsRng);
+
+        let (proof, comm_pk, tag, _) = statement.prove(b"", sk.pk, sk.sk, r);
+
+        // tag of another key
+        let other = SigningKey::new();
+        let (_, _, other_tag, _) = statement.prove(b"", other.pk, other.sk, r);
+        assert!(!statement.verify(b"", &proof, comm_pk, other_tag.as_ref(), None));
+
+        // shifted tag
+        let shifted = tag.unwrap() + curve::g1();
+        assert!(!statement.verify(b"", &proof, comm_pk, Some(&shifted), None));
+    }
+
+    #[test]
+    fn test_prove_verify_unlinkable() {
+        let sk = SigningKey::new();
+
+        let statement = Statement::new(curve::g0(), None);
+
+        let r = Scalar::random(&mut OsRng);
+
+        let (proof, comm_pk, tag, _) = statement.prove(b"", sk.pk, sk.sk, r);
+
+        assert!(tag.is_none());
+        assert!(statement.verify(b"", &proof, comm_pk, None, None));
+
+        // a linkable statement must not accept the proof
+        let linkable = Statement::new(curve::g0(), Some(curve::g1()));
+        assert!(!linkable.verify(b"", &proof, comm_pk, Some(&curve::g1()), None));
+    }
+
+    #[test]
+    fn test_prove_verify_accountable() {
+        let sk = SigningKey::new();
+
+        let opener = curve::Fp::random(&mut OsRng) * curve::g0();
+        let statement = Statement::new_accountable(curve::g0(), Some(curve::g1()), opener);
+
+        let r = Scalar::random(&mut OsRng);
+
+        let (proof, comm_pk, tag, escrow) = statement.prove(b"", sk.pk, sk.sk, r);
+
+        let escrow = escrow.unwrap();
+        assert!(statement.verify(b"", &proof, comm_pk, tag.as_ref(), Some(&escrow)));
+
+        // ciphertext of another key must be rejected
+        let other = SigningKey::new();
+        let forged = Ciphertext {
+            c1: escrow.c1,
+            c2: escrow.c2 - sk.pk + other.pk,
+        };
+        assert!(!statement.verify(b"", &proof, comm_pk, tag.as_ref(), Some(&forged)));
+        assert!(!statement.verify(b"", &proof, comm_pk, tag.as_ref(), None));
+    }
+
+    #[test]
+    fn test_count() {
+        let unlinkable = Statement::new(curve::g0(), None).count();
+        let linkable = Statement::new(curve::g0(), Some(curve::g1())).count();
+        let accountable =
+            Statement::new_accountable(curve::g0(), Some(curve::g1()), curve::g1()).count();
+
+        println!("unlinkable: {:?}", unlinkable);
+        println!("linkable: {:?}", linkable);
+        println!("accountable: {:?}", accountable);
+
+        assert_eq!(linkable.committed, 1);
+        assert!(unlinkable.multipliers < linkable.multipliers);
+        assert!(linkable.multipliers < accountable.multipliers);
+    }
+
+    #[test]
+    fn test_best_window_size() {
+        let statement = Statement::new(curve::g0(), Some(curve::g1()));
+
+        // every exponentiation in the circuit uses a window size with the fewest multipliers
+        for (base, exp) in [
+            (curve::g0(), &statement.g_exp),
+            (curve::g1(), statement.h_exp.as_ref().unwrap()),
+        ]
+        .iter()
+        {
+            for (size, _) in fixexp::Gadget::window_counts(*base) {
+                let other = fixexp::Gadget::new_window(*base, size);
+                assert!(exp.release_multipliers() <= other.release_multipliers());
+            }
+        }
     }
 
     #[bench]
     fn bench_verify(b: &mut Bencher) {
         let sk = SigningKey::new();
 
-        let statement = Statement::new(curve::g0(), curve::g1());
+        let statement = Statement::new(curve::g0(), Some(curve::g1()));
 
         let r = Scalar::random(&mut OsRng);
 
-        let (proof, comm_pk, tag) = statement.prove(sk.pk, sk.sk, r);
+        let (proof, comm_pk, tag, _) = statement.prove(b"", sk.pk, sk.sk, r);
 
-        b.iter(|| assert!(statement.verify(&proof, comm_pk, &tag)))
+        b.iter(|| assert!(statement.verify(b"", &proof, comm_pk, tag.as_ref(), None)))
     }
 }
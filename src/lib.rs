@@ -5,6 +5,9 @@ extern crate test;
 
 mod dlogmv;
 // mod membership;
+pub mod opener;
+pub mod params;
+pub mod ring;
 pub mod unknown;
 
 use dlogmv::gadgets::curve;
@@ -21,7 +24,12 @@ use curve25519_dalek::scalar::Scalar;
 
 use serde::Serialize;
 
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use params::{SecurityParameters, SecurityParametersError};
 use unknown::exppok::ProofOfExp;
+use unknown::fixed::FixedBase;
 
 pub(crate) fn bytes_to_integer(bytes: &[u8]) -> Integer {
     Integer::from_digits(bytes, integer::Order::Lsf)
@@ -40,45 +48,153 @@ pub struct SigningKey {
     sk: curve::Fp,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct PublicKey {
     pk: curve::CurvePoint,
 }
 
+impl PublicKey {
+    // the key as committed to in the unknown-order group
+    fn to_integer(&self) -> Integer {
+        scalar_to_integer(&point_to_scalar(&self.pk))
+    }
+}
+
+/// Selects (at the type level) whether signatures are linkable.
+pub trait Linkability {
+    /// Tag carried by every signature
+    type Tag: Copy + Debug + Eq + Serialize;
+
+    const LINKABLE: bool;
+
+    fn from_point(tag: Option<curve::CurvePoint>) -> Self::Tag;
+
+    fn to_point(tag: &Self::Tag) -> Option<curve::CurvePoint>;
+}
+
+/// Signatures reveal `tag = sk * H(msg)`
+#[derive(Debug)]
+pub enum Linkable {}
+
+/// Signatures carry no tag and are fully anonymous
+#[derive(Debug)]
+pub enum Unlinkable {}
+
+impl Linkability for Linkable {
+    type Tag = curve::CurvePoint;
+
+    const LINKABLE: bool = true;
+
+    fn from_point(tag: Option<curve::CurvePoint>) -> Self::Tag {
+        tag.unwrap()
+    }
+
+    fn to_point(tag: &Self::Tag) -> Option<curve::CurvePoint> {
+        Some(*tag)
+    }
+}
+
+impl Linkability for Unlinkable {
+    type Tag = ();
+
+    const LINKABLE: bool = false;
+
+    fn from_point(_tag: Option<curve::CurvePoint>) -> Self::Tag {}
+
+    fn to_point(_tag: &Self::Tag) -> Option<curve::CurvePoint> {
+        None
+    }
+}
+
 #[derive(Serialize)]
-pub struct Signature<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>> {
-    tag: curve::CurvePoint,     // linkability tag
-    comm1: G::Elem,             // commit to PK
-    comm2: CompressedRistretto, // commit to PK
-    rand: Integer,              // randomness of last commitment
+pub struct Signature<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>, L: Linkability = Linkable> {
+    tag: L::Tag,                        // linkability tag
+    comm1: G::Elem,                     // commit to PK
+    comm2: CompressedRistretto,         // commit to PK
+    escrow: Option<dlogmv::Ciphertext>, // encryption of PK to opener (accountable mode)
+    rand: Integer,                      // randomness of last commitment
     dlogmv: dlogmv::Proof,
     modeq: unknown::base::ModEqProof<G>,
     exp_pi: E,
-    extend: Vec<(G::Elem, unknown::extend::ExtendProof<G>)>,
+    extend: unknown::extend::ExtendChain<G>,
 }
 
-pub struct Context<G: ConvertibleUnknownOrderGroup> {
-    g1: G::Elem,
-    h1: G::Elem,
+pub struct Context<G: ConvertibleUnknownOrderGroup, L: Linkability = Linkable> {
+    g1: G::Elem,      // Fujisaki-Okamoto generators,
+    h1: FixedBase<G>, // powers of h1 are precomputed for the (public) randomness checked by verify
     modeq: unknown::base::RangeModEq<G>,
     dlogmv: dlogmv::Statement,
+    params: SecurityParameters,
+    msg: Vec<u8>, // bound into the transcripts in either mode
+    mode: PhantomData<L>,
 }
 
-impl<G: ConvertibleUnknownOrderGroup> Context<G> {
-    pub fn setup(msg: &[u8]) -> Context<G> {
-        let (g1, h1) = unknown::new_fujisaki_okamoto_gens::<G>();
+impl<G: ConvertibleUnknownOrderGroup, L: Linkability> Context<G, L> {
+    fn tag_base(msg: &[u8]) -> Option<curve::CurvePoint> {
+        if L::LINKABLE {
+            Some(curve::CurvePoint::hash(msg))
+        } else {
+            None
+        }
+    }
 
-        let dlogmv = dlogmv::Statement::new(curve::g0(), curve::CurvePoint::hash(msg));
+    /// Fails if `params` are not valid for the group `G`
+    pub fn setup(
+        params: SecurityParameters,
+        msg: &[u8],
+    ) -> Result<Context<G, L>, SecurityParametersError> {
+        Self::with_statement(
+            params,
+            msg,
+            dlogmv::Statement::new(curve::g0(), Self::tag_base(msg)),
+        )
+    }
+
+    /// Setup for accountable signatures: every signature encrypts the public key of the signer
+    /// under the key of the opener, who can later de-anonymize it.
+    pub fn setup_accountable(
+        params: SecurityParameters,
+        msg: &[u8],
+        opener: &opener::OpenerKey,
+    ) -> Result<Context<G, L>, SecurityParametersError> {
+        Self::with_statement(
+            params,
+            msg,
+            dlogmv::Statement::new_accountable(curve::g0(), Self::tag_base(msg), opener.point()),
+        )
+    }
 
-        let modeq =
-            unknown::base::RangeModEq::new(&g1, &h1, &dlogmv.gens().B, &dlogmv.gens().B_blinding);
+    fn with_statement(
+        params: SecurityParameters,
+        msg: &[u8],
+        dlogmv: dlogmv::Statement,
+    ) -> Result<Context<G, L>, SecurityParametersError> {
+        params.validate::<G>()?;
 
-        Context {
+        let (g1, h1) = unknown::new_fujisaki_okamoto_gens::<G>();
+        let rand_bits = unknown::order_bits::<G>() + params.zk_bits as u32;
+
+        let modeq = unknown::base::RangeModEq::new(
+            &params,
+            &g1,
+            &h1,
+            &dlogmv.gens().B,
+            &dlogmv.gens().B_blinding,
+        );
+
+        Ok(Context {
             modeq,
             dlogmv,
+            params,
+            msg: msg.to_vec(),
             g1,
-            h1,
-        }
+            h1: FixedBase::new(&h1, rand_bits),
+            mode: PhantomData,
+        })
+    }
+
+    pub fn params(&self) -> &SecurityParameters {
+        &self.params
     }
 }
 
@@ -97,24 +213,25 @@ impl SigningKey {
         PublicKey { pk: self.pk }
     }
 
-    pub fn sign<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>>(
+    pub fn sign<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>, L: Linkability>(
         &self,
-        ctx: &mut Context<G>,
-    ) -> Signature<G, E> {
+        ctx: &mut Context<G, L>,
+    ) -> Signature<G, E, L> {
         let pks = point_to_scalar(&self.pk);
         let pki = scalar_to_integer(&pks);
 
-        let rand1 = unknown::random_order::<G>();
+        let rand1 = unknown::random_order::<G>(&ctx.params);
         let rand2 = Scalar::random(&mut OsRng);
 
         let comm1 = G::op(
-            &G::exp(&ctx.g1, &pki),   // G^pk
-            &G::exp(&ctx.h1, &rand1), // H^rand
+            &G::exp_secret(&ctx.g1, &pki), // G^pk
+            &ctx.h1.exp_secret(&rand1),    // H^rand
         );
 
-        let (dlogmv, comm2, tag) = ctx.dlogmv.prove(self.pk, self.sk, rand2);
+        let (dlogmv, comm2, tag, escrow) = ctx.dlogmv.prove(&ctx.msg, self.pk, self.sk, rand2);
 
         let modeq = ctx.modeq.prove(
+            &ctx.msg,
             &comm1,
             &comm2.decompress().unwrap(),
             rand1.clone(),
@@ -122,13 +239,13 @@ impl SigningKey {
             pks,
         );
 
-        let extend: Vec<(G::Elem, unknown::extend::ExtendProof<G>)> = vec![];
-
         Signature {
-            tag,
+            tag: L::from_point(tag),
             comm1,
             comm2,
+            escrow,
             exp_pi: E::new(
+                &ctx.params,
                 &ctx.g1,
                 [self.pk]
                     .iter()
@@ -137,79 +254,134 @@ impl SigningKey {
             rand: rand1,
             dlogmv,
             modeq,
-            extend,
+            extend: unknown::extend::ExtendChain::new(),
         }
     }
 }
 
-impl<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>> Signature<G, E> {
+impl<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>, L: Linkability> Signature<G, E, L> {
     pub fn extend(
         mut self,
-        ctx: &Context<G>,
+        ctx: &Context<G, L>,
         delta: &[PublicKey], // new public keys
         total: &[PublicKey], // total list
-    ) -> Signature<G, E> {
-        let last_comm = self
-            .extend
-            .last()
-            .map(|last| &last.0)
-            .unwrap_or(&self.comm1);
-
-        let keys: Vec<Integer> = delta
-            .iter()
-            .map(|key| scalar_to_integer(&point_to_scalar(&key.pk)))
-            .collect();
-
-        let (new_comm, new_rand, new_proof) = unknown::extend::ExtendProof::<G>::new(
-            &last_comm, // Fujisaki-Okamoto commitment
-            &ctx.h1,    // randomness generator
-            self.rand,  // randomness scalar
-            &keys[..],  // keys to add
+    ) -> Signature<G, E, L> {
+        let keys: Vec<Integer> = delta.iter().map(PublicKey::to_integer).collect();
+
+        self.rand = self.extend.extend(
+            &ctx.params,
+            &self.comm1, // initial Fujisaki-Okamoto commitment
+            &ctx.h1,     // randomness generator
+            self.rand,   // randomness scalar
+            &keys[..],   // keys to add
         );
+        self.exp_pi = E::new(
+            &ctx.params,
+            &ctx.g1,
+            total.iter().map(PublicKey::to_integer),
+        );
+        self
+    }
 
-        self.rand = new_rand;
+    /// Like `extend`, but takes the keys as streams (e.g. from a `ring::RingReader`)
+    /// instead of slices of `PublicKey`.
+    ///
+    /// Memory still grows with the rings: the product of `delta` is kept as the opening
+    /// of the extension and the proof of exponentiation collects the keys of `total`,
+    /// only verification can consume the ring in constant memory (see `verify_stream`).
+    pub fn extend_stream<D, T>(
+        mut self,
+        ctx: &Context<G, L>,
+        delta: D, // new public keys
+        total: T, // total list
+    ) -> Signature<G, E, L>
+    where
+        D: IntoIterator<Item = PublicKey>,
+        T: IntoIterator<Item = PublicKey>,
+    {
+        self.rand = self.extend.extend_iter(
+            &ctx.params,
+            &self.comm1,
+            &ctx.h1,
+            self.rand,
+            delta.into_iter().map(|key| key.to_integer()),
+        );
         self.exp_pi = E::new(
+            &ctx.params,
             &ctx.g1,
-            total
-                .iter()
-                .map(|key| scalar_to_integer(&point_to_scalar(&key.pk))),
+            total.into_iter().map(|key| key.to_integer()),
         );
-        self.extend.push((new_comm, new_proof));
         self
     }
 
-    pub fn verify(&self, ctx: &Context<G>, total: &[PublicKey]) -> Option<curve::CurvePoint> {
+    /// Merges consecutive extensions done by this signer into a single extension
+    pub fn compact(mut self, ctx: &Context<G, L>) -> Signature<G, E, L> {
+        self.extend.compact(&ctx.params, &self.comm1, &ctx.h1);
+        self
+    }
+
+    /// Aggregates the proofs of all extensions, the signature can still be extended afterwards
+    pub fn aggregate(mut self) -> Signature<G, E, L> {
+        self.extend.aggregate();
+        self
+    }
+
+    /// Returns the tag of the signature if valid (the unit type in unlinkable mode)
+    pub fn verify(&self, ctx: &Context<G, L>, total: &[PublicKey]) -> Option<L::Tag> {
+        self.verify_stream(ctx, total.iter().copied())
+    }
+
+    /// Like `verify`, but takes the ring as a stream (e.g. from a `ring::RingReader`),
+    /// `exppok::Proof` and `PokeProof` consume it one key at a time (in constant memory).
+    pub fn verify_stream<T: IntoIterator<Item = PublicKey>>(
+        &self,
+        ctx: &Context<G, L>,
+        total: T,
+    ) -> Option<L::Tag> {
+        // randomness is sampled zk_bits beyond the order bound (checked before any exponentiation)
+        let rand_bits = unknown::order_bits::<G>() + ctx.params.zk_bits as u32;
+        if !unknown::bounded(&self.rand, rand_bits) {
+            println!("bad randomness");
+            return None;
+        }
+        if G::validate(&self.comm1).is_err() {
+            println!("bad commitment");
+            return None;
+        }
+
         // verify base proof: tag valid
-        if !ctx.dlogmv.verify(&self.dlogmv, self.comm2, &self.tag) {
+        if !ctx.dlogmv.verify(
+            &ctx.msg,
+            &self.dlogmv,
+            self.comm2,
+            L::to_point(&self.tag).as_ref(),
+            self.escrow.as_ref(),
+        ) {
             println!("bad dlogmv");
             return None;
         }
 
         // range proof and modeq
         let comm2 = self.comm2.decompress()?;
-        if !ctx.modeq.verify(&self.comm1, &comm2, &self.modeq) {
+        if !ctx.modeq.verify(&ctx.msg, &self.comm1, &comm2, &self.modeq) {
             println!("bad modeq");
             return None;
         }
 
         // verify extensions
-        let mut last_comm = &self.comm1;
-        for (new_comm, proof) in self.extend.iter() {
-            if !proof.verify(last_comm, &ctx.h1, new_comm) {
-                println!("bad extension");
-                return None;
-            }
-            last_comm = new_comm;
+        if !self.extend.verify(&ctx.params, &self.comm1, ctx.h1.base()) {
+            println!("bad extension");
+            return None;
         }
+        let last_comm = self.extend.last().unwrap_or(&self.comm1);
 
         // recompute opened commitment (last)
         let res = self.exp_pi.verify(
+            &ctx.params,
             &ctx.g1,
-            total
-                .iter()
-                .map(|key| scalar_to_integer(&point_to_scalar(&key.pk))),
+            total.into_iter().map(|key| key.to_integer()),
         )?;
-        let res = G::op(&res, &G::exp(&ctx.h1, &self.rand));
+        let res = G::op(&res, &ctx.h1.exp(&self.rand));
 
         // check equality with commitment chain
         if res != last_comm.clone() {
@@ -224,7 +396,10 @@ impl<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>> Signature<G, E> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use accumulator::group::{ClassGroup, Rsa3072};
+    use accumulator::group::{
+        Class, ClassGroup, DiscriminantCell, Rsa3072, Rsa4096, SeededDiscriminant,
+        UnknownOrderGroup,
+    };
 
     use test::Bencher;
 
@@ -235,7 +410,7 @@ mod tests {
     fn sign_verify<G: ConvertibleUnknownOrderGroup>() {
         let msg: &[u8] = &[];
 
-        let mut ctx = Context::<G>::setup(msg);
+        let mut ctx = Context::<G>::setup(SecurityParameters::for_group::<G>(), msg).unwrap();
         let sk = SigningKey::new();
         let pk = sk.pk();
 
@@ -252,6 +427,132 @@ mod tests {
         assert!(sig.verify(&ctx, &total[..]).is_some());
     }
 
+    fn sign_verify_compact<G: ConvertibleUnknownOrderGroup>() {
+        let msg: &[u8] = &[];
+
+        let mut ctx = Context::<G>::setup(SecurityParameters::for_group::<G>(), msg).unwrap();
+        let sk = SigningKey::new();
+        let pk = sk.pk();
+
+        let mut sig: Signature<G, unknown::exppok::Proof<G>> = sk.sign(&mut ctx);
+        let mut total = vec![pk];
+        for _ in 0..3 {
+            let delta: Vec<PublicKey> = (0..5).map(|_| SigningKey::new().pk()).collect();
+            total.extend(delta.iter());
+            sig = sig.extend(&ctx, &delta[..], &total[..]);
+        }
+
+        let sig = sig.compact(&ctx);
+        assert_eq!(sig.extend.len(), 1);
+        assert!(sig.verify(&ctx, &total[..]).is_some());
+
+        let sig = sig.aggregate();
+        assert!(sig.verify(&ctx, &total[..]).is_some());
+
+        let delta: Vec<PublicKey> = (0..5).map(|_| SigningKey::new().pk()).collect();
+        total.extend(delta.iter());
+        let sig = sig.extend(&ctx, &delta[..], &total[..]);
+        assert!(sig.verify(&ctx, &total[..]).is_some());
+    }
+
+    fn sign_verify_stream<G: ConvertibleUnknownOrderGroup>() {
+        let msg: &[u8] = &[];
+
+        let mut ctx = Context::<G>::setup(SecurityParameters::for_group::<G>(), msg).unwrap();
+        let sk = SigningKey::new();
+        let pk = sk.pk();
+
+        let sig: Signature<G, unknown::exppok::Proof<G>> = sk.sign(&mut ctx);
+
+        // rings serialized to (in-memory) files
+        let delta: Vec<PublicKey> = (0..100).map(|_| SigningKey::new().pk()).collect();
+        let mut delta_file = vec![];
+        ring::write_ring(&mut delta_file, delta.iter().copied()).unwrap();
+        let mut total_file = vec![];
+        ring::write_ring(&mut total_file, delta.iter().copied().chain(Some(pk))).unwrap();
+
+        let read = |file: &Vec<u8>| ring::RingReader::new(&file[..]).map(|key| key.unwrap());
+
+        let sig = sig.extend_stream(&ctx, read(&delta_file), read(&total_file));
+        assert!(sig.verify_stream(&ctx, read(&total_file)).is_some());
+
+        let mut total = delta.clone();
+        total.push(pk);
+        assert!(sig.verify(&ctx, &total[..]).is_some());
+        assert!(sig.verify(&ctx, &delta[..]).is_none());
+    }
+
+    fn verify_oversized<G: ConvertibleUnknownOrderGroup>() {
+        let msg: &[u8] = &[];
+
+        let mut ctx = Context::<G>::setup(SecurityParameters::for_group::<G>(), msg).unwrap();
+        let sk = SigningKey::new();
+        let pk = sk.pk();
+
+        let mut sig: Signature<G, unknown::exppok::Proof<G>> = sk.sign(&mut ctx);
+        assert!(sig.verify(&ctx, &[pk]).is_some());
+
+        sig.rand += Integer::from(1) << 8192;
+        assert!(sig.verify(&ctx, &[pk]).is_none());
+    }
+
+    #[test]
+    fn verify_invalid_elem_rsa() {
+        let msg: &[u8] = &[];
+
+        let mut ctx =
+            Context::<Rsa3072>::setup(SecurityParameters::for_group::<Rsa3072>(), msg).unwrap();
+        let sk = SigningKey::new();
+        let pk = sk.pk();
+
+        let mut sig: Signature<Rsa3072, unknown::exppok::Proof<Rsa3072>> = sk.sign(&mut ctx);
+        assert!(sig.verify(&ctx, &[pk]).is_some());
+
+        // N - 2 is the same coset as 2, but not its canonical representative
+        let n = Rsa3072::rsa_modulus().unwrap();
+        let raw: rug_binserial::Integer = (n - 2).into();
+        sig.comm1 = bincode::deserialize(&bincode::serialize(&raw).unwrap()).unwrap();
+        assert!(Rsa3072::validate(&sig.comm1).is_err());
+        assert!(sig.verify(&ctx, &[pk]).is_none());
+    }
+
+    fn sign_verify_unlinkable<G: ConvertibleUnknownOrderGroup>() {
+        let msg: &[u8] = &[];
+
+        let mut ctx =
+            Context::<G, Unlinkable>::setup(SecurityParameters::for_group::<G>(), msg).unwrap();
+        let sk = SigningKey::new();
+        let pk = sk.pk();
+
+        let sig: Signature<G, unknown::exppok::Proof<G>, Unlinkable> = sk.sign(&mut ctx);
+
+        assert!(sig.verify(&ctx, &[pk]).is_some());
+
+        let sks: Vec<SigningKey> = (0..10).map(|_| SigningKey::new()).collect();
+        let delta: Vec<PublicKey> = sks.iter().map(|sk| sk.pk()).collect();
+        let mut total = delta.clone();
+        total.push(pk);
+        let sig = sig.extend(&ctx, &delta[..], &total[..]);
+
+        assert!(sig.verify(&ctx, &total[..]).is_some());
+    }
+
+    #[test]
+    fn verify_other_msg_unlinkable() {
+        let params = SecurityParameters::for_group::<Rsa3072>();
+        let mut ctx = Context::<Rsa3072, Unlinkable>::setup(params, b"A").unwrap();
+        let sk = SigningKey::new();
+        let pk = sk.pk();
+
+        let sig: Signature<Rsa3072, unknown::exppok::Proof<Rsa3072>, Unlinkable> =
+            sk.sign(&mut ctx);
+        assert!(sig.verify(&ctx, &[pk]).is_some());
+
+        // same generators, only the message differs (there is no tag to bind it in this mode)
+        ctx.msg = b"B".to_vec();
+        assert!(sig.verify(&ctx, &[pk]).is_none());
+    }
+
     fn bench_sign<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>>(
         b: &mut Bencher,
         num_extend: usize, // how many extensions
@@ -262,7 +563,7 @@ mod tests {
         println!("num_extend: {}", num_extend);
         println!("num_keys: {}", num_keys);
 
-        let mut ctx = Context::<G>::setup(msg);
+        let mut ctx = Context::<G>::setup(SecurityParameters::for_group::<G>(), msg).unwrap();
         let sk = SigningKey::new();
         let pk = sk.pk();
 
@@ -297,7 +598,7 @@ mod tests {
         println!("num_extend: {}", num_extend);
         println!("num_keys: {}", num_keys);
 
-        let mut ctx = Context::<G>::setup(msg);
+        let mut ctx = Context::<G>::setup(SecurityParameters::for_group::<G>(), msg).unwrap();
 
         #[cfg(debug_assertions)]
         println!("signing");
@@ -376,11 +677,77 @@ mod tests {
         sign_verify::<Rsa3072>();
     }
 
+    #[test]
+    fn sign_verify_rsa4096() {
+        sign_verify::<Rsa4096>();
+    }
+
     #[test]
     fn sign_verify_classgroup() {
         sign_verify::<ClassGroup>();
     }
 
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    enum TestDiscriminant {}
+
+    impl SeededDiscriminant for TestDiscriminant {
+        const SEED: &'static [u8] = b"extend_sig test discriminant";
+        const BITS: u32 = 1024;
+
+        fn cell() -> &'static DiscriminantCell {
+            static CELL: DiscriminantCell = DiscriminantCell::new();
+            &CELL
+        }
+    }
+
+    #[test]
+    fn sign_verify_seeded_classgroup() {
+        sign_verify::<Class<TestDiscriminant>>();
+    }
+
+    #[test]
+    fn sign_verify_compact_rsa() {
+        sign_verify_compact::<Rsa3072>();
+    }
+
+    #[test]
+    fn sign_verify_stream_rsa() {
+        sign_verify_stream::<Rsa3072>();
+    }
+
+    #[test]
+    fn verify_oversized_rsa() {
+        verify_oversized::<Rsa3072>();
+    }
+
+    #[test]
+    fn verify_oversized_classgroup() {
+        verify_oversized::<ClassGroup>();
+    }
+
+    #[test]
+    fn sign_verify_unlinkable_rsa() {
+        sign_verify_unlinkable::<Rsa3072>();
+    }
+
+    #[test]
+    fn setup_invalid_params() {
+        let msg: &[u8] = &[];
+
+        let params = SecurityParameters::for_group::<Rsa4096>();
+        assert_eq!(
+            Context::<Rsa3072>::setup(params, msg).err(),
+            Some(SecurityParametersError::GroupTooSmall(3072))
+        );
+
+        let mut params = SecurityParameters::for_group::<Rsa3072>();
+        params.zk_bits = 128;
+        assert_eq!(
+            Context::<Rsa3072>::setup(params, msg).err(),
+            Some(SecurityParametersError::ModEq)
+        );
+    }
+
     #[bench]
     fn bench_verify_rsa(b: &mut Bencher) {
         bench_verify_args::<Rsa3072>(b);
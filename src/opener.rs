@@ -0,0 +1,229 @@
+//! Designated opener for accountable signatures.
+//!
+//! In accountable mode every signature carries an ElGamal encryption (over the inner curve)
+//! of the public key of the signer under the key of the opener,
+//! the dlogmv circuit proves that it encrypts the same key as committed in the signature.
+use cpsnarks_set::utils::ConvertibleUnknownOrderGroup;
+
+use merlin::Transcript;
+
+use rand_core::OsRng;
+
+use crate::dlogmv::gadgets::curve;
+use crate::unknown::exppok::ProofOfExp;
+use crate::{Context, Linkability, PublicKey, Signature};
+
+const TRANSCRIPT_SEP: &'static [u8] = b"ZK_OPEN";
+
+pub struct Opener {
+    pk: curve::CurvePoint,
+    sk: curve::Fp,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OpenerKey {
+    pk: curve::CurvePoint,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OpenError {
+    /// The signature (or the context) is not in accountable mode
+    NotAccountable,
+    /// The context is accountable to another opener
+    WrongOpener,
+    /// The signature does not verify for the ring
+    InvalidSignature,
+}
+
+/// Proof that the opener decrypted correctly:
+/// log_g(opener) = log_c1(c2 - pk) (Chaum-Pedersen)
+pub struct OpeningProof {
+    a1: curve::CurvePoint,
+    a2: curve::CurvePoint,
+    z: curve::Fp,
+}
+
+fn append_point(transcript: &mut Transcript, label: &'static [u8], point: &curve::CurvePoint) {
+    transcript.append_message(label, point.x.as_bytes());
+    transcript.append_message(label, point.y.as_bytes());
+}
+
+fn challenge(
+    opener: &curve::CurvePoint,
+    c1: &curve::CurvePoint,
+    c2: &curve::CurvePoint,
+    pk: &curve::CurvePoint,
+    a1: &curve::CurvePoint,
+    a2: &curve::CurvePoint,
+) -> curve::Fp {
+    let mut transcript = Transcript::new(TRANSCRIPT_SEP);
+    append_point(&mut transcript, b"opener", opener);
+    append_point(&mut transcript, b"c1", c1);
+    append_point(&mut transcript, b"c2", c2);
+    append_point(&mut transcript, b"pk", pk);
+    append_point(&mut transcript, b"a1", a1);
+    append_point(&mut transcript, b"a2", a2);
+
+    // same range as Fp::random
+    let mut bytes: [u8; 32] = [0; 32];
+    transcript.challenge_bytes(b"challenge", &mut bytes);
+    bytes[0] = 0;
+    bytes.into()
+}
+
+impl OpenerKey {
+    pub(crate) fn point(&self) -> curve::CurvePoint {
+        self.pk
+    }
+}
+
+impl Opener {
+    pub fn new() -> Self {
+        let sk = curve::Fp::random(&mut OsRng);
+        let pk = sk * curve::g0();
+        Opener { pk, sk }
+    }
+
+    pub fn key(&self) -> OpenerKey {
+        OpenerKey { pk: self.pk }
+    }
+
+    /// Verifies the signature for the ring `total` under the accountable context `ctx`
+    /// and decrypts the public key of the signer.
+    /// Only verified signatures are opened: the ciphertext of an invalid signature
+    /// need not encrypt the key of any signer.
+    pub fn open<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>, L: Linkability>(
+        &self,
+        ctx: &Context<G, L>,
+        sig: &Signature<G, E, L>,
+        total: &[PublicKey],
+    ) -> Result<PublicKey, OpenError> {
+        let opener = ctx.dlogmv.opener().ok_or(OpenError::NotAccountable)?;
+        if opener != self.pk {
+            return Err(OpenError::WrongOpener);
+        }
+        let escrow = sig.escrow.as_ref().ok_or(OpenError::NotAccountable)?;
+        sig.verify(ctx, total).ok_or(OpenError::InvalidSignature)?;
+        Ok(PublicKey {
+            pk: escrow.c2 - self.sk * escrow.c1,
+        })
+    }
+
+    /// Like `open`, and proves that the decryption is correct.
+    pub fn prove_opening<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>, L: Linkability>(
+        &self,
+        ctx: &Context<G, L>,
+        sig: &Signature<G, E, L>,
+        total: &[PublicKey],
+    ) -> Result<(PublicKey, OpeningProof), OpenError> {
+        let pk = self.open(ctx, sig, total)?;
+        let escrow = sig.escrow.as_ref().ok_or(OpenError::NotAccountable)?;
+
+        // first round message
+        let k = curve::Fp::random(&mut OsRng);
+        let a1 = k * curve::g0();
+        let a2 = k * escrow.c1;
+
+        // compute response
+        let e = challenge(&self.pk, &escrow.c1, &escrow.c2, &pk.pk, &a1, &a2);
+        let z = k + e * self.sk;
+
+        Ok((pk, OpeningProof { a1, a2, z }))
+    }
+}
+
+impl OpeningProof {
+    pub fn verify<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>, L: Linkability>(
+        &self,
+        opener: &OpenerKey,
+        sig: &Signature<G, E, L>,
+        pk: &PublicKey,
+    ) -> bool {
+        let escrow = match sig.escrow.as_ref() {
+            Some(escrow) => escrow,
+            None => return false,
+        };
+
+        let e = challenge(
+            &opener.pk, &escrow.c1, &escrow.c2, &pk.pk, &self.a1, &self.a2,
+        );
+
+        // z * g = a1 + e * opener
+        let left1 = self.z * curve::g0();
+        let right1 = self.a1 + e * opener.pk;
+
+        // z * c1 = a2 + e * (c2 - pk)
+        let left2 = self.z * escrow.c1;
+        let right2 = self.a2 + e * (escrow.c2 - pk.pk);
+
+        left1 == right1 && left2 == right2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::params::SecurityParameters;
+    use crate::unknown::exppok;
+    use crate::SigningKey;
+
+    use accumulator::group::Rsa2048;
+
+    #[test]
+    fn test_open() {
+        let msg: &[u8] = &[];
+
+        let opener = Opener::new();
+        let mut ctx = Context::<Rsa2048>::setup_accountable(
+            SecurityParameters::for_group::<Rsa2048>(),
+            msg,
+            &opener.key(),
+        )
+        .unwrap();
+
+        let sk = SigningKey::new();
+        let pk = sk.pk();
+
+        let sig: Signature<Rsa2048, exppok::Proof<Rsa2048>> = sk.sign(&mut ctx);
+        assert!(sig.verify(&ctx, &[pk]).is_some());
+
+        let (opened, proof) = opener.prove_opening(&ctx, &sig, &[pk]).unwrap();
+        assert_eq!(opened, pk);
+        assert!(proof.verify(&opener.key(), &sig, &pk));
+
+        // proof does not verify for another key
+        let other = SigningKey::new().pk();
+        assert!(!proof.verify(&opener.key(), &sig, &other));
+
+        // signatures are only opened if valid for the ring
+        assert_eq!(
+            opener.open(&ctx, &sig, &[other]),
+            Err(OpenError::InvalidSignature)
+        );
+
+        // nor by another opener
+        assert_eq!(
+            Opener::new().open(&ctx, &sig, &[pk]),
+            Err(OpenError::WrongOpener)
+        );
+    }
+
+    #[test]
+    fn test_open_non_accountable() {
+        let msg: &[u8] = &[];
+
+        let opener = Opener::new();
+        let mut ctx =
+            Context::<Rsa2048>::setup(SecurityParameters::for_group::<Rsa2048>(), msg).unwrap();
+
+        let sk = SigningKey::new();
+        let pk = sk.pk();
+        let sig: Signature<Rsa2048, exppok::Proof<Rsa2048>> = sk.sign(&mut ctx);
+
+        assert_eq!(
+            opener.open(&ctx, &sig, &[pk]),
+            Err(OpenError::NotAccountable)
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Security parameters shared by all sub-protocols of a signature.
+//!
+//! Sizes fixed by the curves (the field of the Pedersen commitments and the inner curve used for
+//! the keys) are not configurable, `SecurityParameters::validate` checks that the configurable
+//! ones fit them.
+use cpsnarks_set::parameters::Parameters;
+use cpsnarks_set::utils::curve::Field;
+use cpsnarks_set::utils::ConvertibleUnknownOrderGroup;
+
+use curve25519_dalek::scalar::Scalar;
+
+use crate::dlogmv::gadgets::curve;
+
+/// The unknown-order group a context is set up for (and its minimum size)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupChoice {
+    Rsa { modulus_bits: u32 },
+    Class { discriminant_bits: u32 },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecurityParameters {
+    pub challenge_bits: u16, // Fiat-Shamir challenges (soundness)
+    pub prime_bits: u16,     // prime challenges of the proofs of exponentiation and ZKPokRep
+    pub zk_bits: u16,        // statistical slack of the masks (zero-knowledge)
+    pub group: GroupChoice,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SecurityParametersError {
+    /// Challenge and slack do not fit the range of the Pedersen commitment field (see ModEq)
+    ModEq,
+    /// Prime challenges are shorter than the challenges
+    PrimeTooShort,
+    /// The group is of a different kind than `GroupChoice`
+    GroupMismatch,
+    /// The group is smaller than `GroupChoice` (actual bits)
+    GroupTooSmall(u32),
+}
+
+impl GroupChoice {
+    /// The kind and size of `G`
+    pub fn of<G: ConvertibleUnknownOrderGroup>() -> Self {
+        match G::rsa_modulus() {
+            Ok(modulus) => GroupChoice::Rsa {
+                modulus_bits: modulus.significant_bits(),
+            },
+            // the order bound is sqrt(|D|) + 1
+            Err(_) => GroupChoice::Class {
+                discriminant_bits: 2 * G::order_upper_bound().significant_bits(),
+            },
+        }
+    }
+}
+
+impl SecurityParameters {
+    /// Parameters derived from the Pedersen commitment field (like `Parameters::from_curve`)
+    pub fn new(group: GroupChoice) -> Self {
+        let security_level = (Scalar::size_in_bits() / 2) as u16;
+        SecurityParameters {
+            challenge_bits: security_level - 2,
+            prime_bits: 128,
+            zk_bits: security_level - 3,
+            group,
+        }
+    }
+
+    /// Like `new`, for the group `G` as is
+    pub fn for_group<G: ConvertibleUnknownOrderGroup>() -> Self {
+        Self::new(GroupChoice::of::<G>())
+    }
+
+    /// Parameters of the ModEq proof between the two commitments to the public key
+    pub fn modeq(&self) -> Parameters {
+        let field_size_bits = Scalar::size_in_bits() as u16;
+        Parameters {
+            security_level: field_size_bits / 2,
+            security_zk: self.zk_bits,
+            security_soundness: self.challenge_bits,
+            field_size_bits,
+            hash_to_prime_bits: curve::FP_INNER_BITS as u16,
+        }
+    }
+
+    pub fn validate<G: ConvertibleUnknownOrderGroup>(&self) -> Result<(), SecurityParametersError> {
+        if self.modeq().is_valid().is_err() {
+            return Err(SecurityParametersError::ModEq);
+        }
+        if self.prime_bits < self.challenge_bits {
+            return Err(SecurityParametersError::PrimeTooShort);
+        }
+        let (bits, actual) = match (self.group, GroupChoice::of::<G>()) {
+            (
+                GroupChoice::Rsa { modulus_bits },
+                GroupChoice::Rsa {
+                    modulus_bits: actual,
+                },
+            ) => (modulus_bits, actual),
+            (
+                GroupChoice::Class { discriminant_bits },
+                GroupChoice::Class {
+                    discriminant_bits: actual,
+                },
+            ) => (discriminant_bits, actual),
+            _ => return Err(SecurityParametersError::GroupMismatch),
+        };
+        if actual < bits {
+            return Err(SecurityParametersError::GroupTooSmall(actual));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use accumulator::group::{ClassGroup, Rsa2048, Rsa3072};
+
+    #[test]
+    fn test_default() {
+        let params = SecurityParameters::for_group::<Rsa3072>();
+        assert_eq!(params.validate::<Rsa3072>(), Ok(()));
+        assert_eq!(params.group, GroupChoice::Rsa { modulus_bits: 3072 });
+
+        // the defaults match those of cpsnarks-set
+        let (modeq, _) = Parameters::from_curve::<Scalar>().unwrap();
+        assert_eq!(params.modeq().security_zk, modeq.security_zk);
+        assert_eq!(params.modeq().security_soundness, modeq.security_soundness);
+        assert_eq!(params.modeq().hash_to_prime_bits, modeq.hash_to_prime_bits);
+
+        let params = SecurityParameters::for_group::<ClassGroup>();
+        assert_eq!(params.validate::<ClassGroup>(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate() {
+        let params = SecurityParameters::for_group::<Rsa3072>();
+        assert_eq!(
+            params.validate::<Rsa2048>(),
+            Err(SecurityParametersError::GroupTooSmall(2048))
+        );
+        assert_eq!(
+            params.validate::<ClassGroup>(),
+            Err(SecurityParametersError::GroupMismatch)
+        );
+
+        let mut params = SecurityParameters::for_group::<Rsa2048>();
+        params.challenge_bits = 128;
+        assert_eq!(
+            params.validate::<Rsa2048>(),
+            Err(SecurityParametersError::ModEq)
+        );
+
+        let mut params = SecurityParameters::for_group::<Rsa2048>();
+        params.prime_bits = 64;
+        assert_eq!(
+            params.validate::<Rsa2048>(),
+            Err(SecurityParametersError::PrimeTooShort)
+        );
+    }
+}
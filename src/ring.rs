@@ -0,0 +1,146 @@
+//! Reading and writing (large) rings of public keys as a stream,
+//! every key is stored as the 64 bytes x || y of the curve point.
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use curve25519_dalek::scalar::Scalar;
+
+use crate::{curve, PublicKey};
+
+pub const KEY_SIZE: usize = 64;
+
+// number of keys buffered per read/write
+const CHUNK_KEYS: usize = 4096;
+
+impl PublicKey {
+    pub fn to_bytes(&self) -> [u8; KEY_SIZE] {
+        let mut bytes = [0u8; KEY_SIZE];
+        bytes[..32].copy_from_slice(self.pk.x.as_bytes());
+        bytes[32..].copy_from_slice(self.pk.y.as_bytes());
+        bytes
+    }
+
+    /// Returns None if the bytes do not encode a point on the curve
+    pub fn from_bytes(bytes: &[u8; KEY_SIZE]) -> Option<Self> {
+        let mut x = [0u8; 32];
+        let mut y = [0u8; 32];
+        x.copy_from_slice(&bytes[..32]);
+        y.copy_from_slice(&bytes[32..]);
+        let pk = curve::CurvePoint::new(
+            Scalar::from_canonical_bytes(x)?,
+            Scalar::from_canonical_bytes(y)?,
+        );
+        if pk.on_curve() {
+            Some(PublicKey { pk })
+        } else {
+            None
+        }
+    }
+}
+
+/// Iterator over the public keys of a ring read from a reader
+pub struct RingReader<R: Read> {
+    reader: BufReader<R>,
+}
+
+impl<R: Read> RingReader<R> {
+    pub fn new(reader: R) -> Self {
+        RingReader {
+            reader: BufReader::with_capacity(CHUNK_KEYS * KEY_SIZE, reader),
+        }
+    }
+}
+
+impl<R: Read> Iterator for RingReader<R> {
+    type Item = io::Result<PublicKey>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut bytes = [0u8; KEY_SIZE];
+        let mut read = 0;
+        while read < KEY_SIZE {
+            match self.reader.read(&mut bytes[read..]) {
+                Ok(0) if read == 0 => return None,
+                Ok(0) => return Some(Err(io::ErrorKind::UnexpectedEof.into())),
+                Ok(n) => read += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        Some(
+            PublicKey::from_bytes(&bytes)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid public key")),
+        )
+    }
+}
+
+/// Writes the keys of a ring, can be read back using `RingReader`
+pub fn write_ring<W: Write, I: IntoIterator<Item = PublicKey>>(
+    writer: W,
+    keys: I,
+) -> io::Result<()> {
+    let mut writer = BufWriter::with_capacity(CHUNK_KEYS * KEY_SIZE, writer);
+    for key in keys {
+        writer.write_all(&key.to_bytes())?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::params::SecurityParameters;
+    use crate::unknown::exppok::{Proof, ProofOfExp};
+    use crate::SigningKey;
+
+    use accumulator::group::{Rsa2048, UnknownOrderGroup};
+
+    use rug::rand::RandState;
+
+    #[test]
+    fn test_read_write() {
+        let keys: Vec<PublicKey> = (0..10).map(|_| SigningKey::new().pk()).collect();
+
+        let mut buf = vec![];
+        write_ring(&mut buf, keys.iter().copied()).unwrap();
+        assert_eq!(buf.len(), keys.len() * KEY_SIZE);
+
+        let read: Vec<PublicKey> = RingReader::new(&buf[..]).map(|key| key.unwrap()).collect();
+        assert_eq!(read, keys);
+
+        // truncated input
+        let mut reader = RingReader::new(&buf[..KEY_SIZE + 10]);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+
+        // not on the curve
+        let mut bad = keys[0].to_bytes();
+        bad[0] ^= 1;
+        assert!(PublicKey::from_bytes(&bad).is_none());
+    }
+
+    #[test]
+    fn test_stream_larger_than_buffer() {
+        let params = SecurityParameters::for_group::<Rsa2048>();
+        let mut rand = RandState::new();
+        let base = Rsa2048::unknown_possibly_random_order_elem(&mut rand);
+
+        // more keys than a single buffer of the reader holds
+        let keys: Vec<PublicKey> = (0..10).map(|_| SigningKey::new().pk()).collect();
+        let ring: Vec<PublicKey> = keys.iter().copied().cycle().take(CHUNK_KEYS + 1).collect();
+        let mut buf = vec![];
+        write_ring(&mut buf, ring.iter().copied()).unwrap();
+        assert!(buf.len() > CHUNK_KEYS * KEY_SIZE);
+
+        let read = || RingReader::new(&buf[..]).map(|key| key.unwrap().to_integer());
+        let proof = Proof::<Rsa2048>::new(&params, &base, ring.iter().map(PublicKey::to_integer));
+        let expected = proof.verify(&params, &base, ring.iter().map(PublicKey::to_integer));
+        assert!(expected.is_some());
+        assert_eq!(proof.verify(&params, &base, read()), expected);
+
+        // the last key (beyond the first buffer) is part of the statement
+        let mut other = buf.clone();
+        other.truncate(CHUNK_KEYS * KEY_SIZE);
+        let read_other = RingReader::new(&other[..]).map(|key| key.unwrap().to_integer());
+        assert_ne!(proof.verify(&params, &base, read_other), expected);
+    }
+}
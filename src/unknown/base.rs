@@ -15,7 +15,6 @@ use merlin::Transcript;
 
 use cpsnarks_set::commitments::integer::IntegerCommitment;
 use cpsnarks_set::commitments::pedersen::PedersenCommitment;
-use cpsnarks_set::parameters::Parameters;
 use cpsnarks_set::utils::ConvertibleUnknownOrderGroup;
 
 use curve25519_dalek::ristretto::RistrettoPoint;
@@ -27,6 +26,7 @@ use std::cell::RefCell;
 
 use serde::Serialize;
 
+use crate::params::SecurityParameters;
 use crate::scalar_to_integer;
 
 const TRANSCRIPT_SEP: &'static [u8] = b"ZK_POK_MODEQ";
@@ -44,6 +44,7 @@ pub struct ModEqProof<G: ConvertibleUnknownOrderGroup> {
 
 impl<G: ConvertibleUnknownOrderGroup> RangeModEq<G> {
     pub fn new(
+        params: &SecurityParameters,
         gen_g1: &G::Elem,
         gen_h1: &G::Elem,
         gen_g2: &RistrettoPoint,
@@ -54,7 +55,7 @@ impl<G: ConvertibleUnknownOrderGroup> RangeModEq<G> {
             rng2: OsRng,
             protocol: Protocol {
                 crs: CRSModEq::<G, RistrettoPoint> {
-                    parameters: Parameters::from_curve::<Scalar>().unwrap().0,
+                    parameters: params.modeq(),
                     integer_commitment_parameters: IntegerCommitment::new(gen_g1, gen_h1),
                     pedersen_commitment_parameters: PedersenCommitment::new(gen_g2, gen_h2),
                 },
@@ -64,13 +65,16 @@ impl<G: ConvertibleUnknownOrderGroup> RangeModEq<G> {
 
     pub fn prove(
         &mut self,
+        msg: &[u8],             // message signed
         comm1: &G::Elem,        // commitment in group 1
         comm2: &RistrettoPoint, // commitment in group 2
         rand1: Integer,
         rand2: Scalar,
         value: Scalar,
     ) -> ModEqProof<G> {
-        let proof_transcript = RefCell::new(Transcript::new(TRANSCRIPT_SEP));
+        let mut transcript = Transcript::new(TRANSCRIPT_SEP);
+        transcript.append_message(b"msg", msg);
+        let proof_transcript = RefCell::new(transcript);
         let mut verifier_channel =
             TranscriptVerifierChannel::new(&self.protocol.crs, &proof_transcript);
 
@@ -97,11 +101,14 @@ impl<G: ConvertibleUnknownOrderGroup> RangeModEq<G> {
 
     pub fn verify(
         &self,
+        msg: &[u8],             // message signed
         comm1: &G::Elem,        // commitment in group 1
         comm2: &RistrettoPoint, // commitment in group 2
         proof: &ModEqProof<G>,
     ) -> bool {
-        let verification_transcript = RefCell::new(Transcript::new(TRANSCRIPT_SEP));
+        let mut transcript = Transcript::new(TRANSCRIPT_SEP);
+        transcript.append_message(b"msg", msg);
+        let verification_transcript = RefCell::new(transcript);
         let mut prover_channel = TranscriptProverChannel::new(
             &self.protocol.crs,
             &verification_transcript,
@@ -131,13 +138,14 @@ mod tests {
     use accumulator::group::{ClassGroup, Rsa2048};
 
     fn test_prove_verify<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
         let (gen_g1, gen_h1) = new_fujisaki_okamoto_gens::<G>();
         let pedersen = PedersenGens::default();
 
         let mut protocol =
-            RangeModEq::<G>::new(&gen_g1, &gen_h1, &pedersen.B, &pedersen.B_blinding);
+            RangeModEq::<G>::new(&params, &gen_g1, &gen_h1, &pedersen.B, &pedersen.B_blinding);
 
-        let rand1 = random_order::<G>();
+        let rand1 = random_order::<G>(&params);
         let rand2 = Scalar::random(&mut OsRng);
 
         let value = Scalar::from(1337u32);
@@ -147,9 +155,52 @@ mod tests {
 
         let comm2 = pedersen.commit(value, rand2);
 
-        let proof = protocol.prove(&comm1, &comm2, rand1, rand2, value);
+        let proof = protocol.prove(b"msg", &comm1, &comm2, rand1, rand2, value);
 
-        assert!(protocol.verify(&comm1, &comm2, &proof));
+        assert!(protocol.verify(b"msg", &comm1, &comm2, &proof));
+        assert!(!protocol.verify(b"other", &comm1, &comm2, &proof));
+    }
+
+    fn test_oversized<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
+        let (gen_g1, gen_h1) = new_fujisaki_okamoto_gens::<G>();
+        let pedersen = PedersenGens::default();
+
+        let mut protocol =
+            RangeModEq::<G>::new(&params, &gen_g1, &gen_h1, &pedersen.B, &pedersen.B_blinding);
+
+        let rand1 = random_order::<G>(&params);
+        let rand2 = Scalar::random(&mut OsRng);
+        let value = Scalar::from(1337u32);
+
+        let comm1 = G::op(
+            &G::exp(&gen_g1, &scalar_to_integer(&value)),
+            &G::exp(&gen_h1, &rand1),
+        );
+        let comm2 = pedersen.commit(value, rand2);
+
+        let proof = protocol.prove(b"", &comm1, &comm2, rand1, rand2, value);
+        let huge = Integer::from(1) << 8192;
+
+        let mut bad = ModEqProof {
+            proof: proof.proof.clone(),
+        };
+        bad.proof.message2.s_e += &huge;
+        assert!(!protocol.verify(b"", &comm1, &comm2, &bad));
+
+        let mut bad = ModEqProof { proof: proof.proof };
+        bad.proof.message2.s_r += &huge;
+        assert!(!protocol.verify(b"", &comm1, &comm2, &bad));
+    }
+
+    #[test]
+    fn test_oversized_rsa() {
+        test_oversized::<Rsa2048>();
+    }
+
+    #[test]
+    fn test_oversized_classgroup() {
+        test_oversized::<ClassGroup>();
     }
 
     #[test]
@@ -3,29 +3,90 @@ use cpsnarks_set::utils::ConvertibleUnknownOrderGroup;
 
 use rug::Integer;
 
+use crate::params::SecurityParameters;
+use crate::unknown::bounded;
+use crate::unknown::reduce::{product, Reducible};
+
 use merlin::Transcript;
 
 use serde::Serialize;
 
-const TRANSCRIPT_SEP: &'static [u8] = b"ZK_POK_EXP";
+#[allow(non_snake_case)]
+mod pietrzak;
+
+#[allow(non_snake_case)]
+mod poke;
+
+#[allow(non_snake_case)]
+mod wesolowski;
 
-const STAT_SECURITY: usize = 128;
+pub use pietrzak::PietrzakProof;
+pub use poke::PokeProof;
+pub use wesolowski::WesolowskiProof;
+
+const TRANSCRIPT_SEP: &'static [u8] = b"ZK_POK_EXP";
 
 pub trait ProofOfExp<G: ConvertibleUnknownOrderGroup>: Serialize {
-    fn new<I: Iterator<Item = Integer>>(base: &G::Elem, keys: I) -> Self;
+    fn new<I: Iterator<Item = Integer>>(
+        params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Self;
+
+    fn verify<I: Iterator<Item = Integer>>(
+        &self,
+        params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Option<G::Elem>;
+}
 
-    fn verify<I: Iterator<Item = Integer>>(&self, base: &G::Elem, keys: I) -> Option<G::Elem>;
+// product of the keys
+fn exponent<K: std::borrow::Borrow<Integer>, I: Iterator<Item = K>>(keys: I) -> Integer {
+    let keys: Vec<Integer> = keys.map(|key| key.borrow().clone()).collect();
+    product(&keys[..])
+}
+
+// base^(product of the keys) = Q^d * base^r for the reduction (Q, r) modulo d = 2^prime_bits,
+// like the quotients of the proofs, this never multiplies out the keys (see reduce.rs)
+fn power<G: ConvertibleUnknownOrderGroup>(
+    params: &SecurityParameters,
+    base: &G::Elem,
+    keys: &[Integer],
+) -> G::Elem {
+    let d = Integer::from(1) << params.prime_bits as u32;
+    let (Q, r) = Reducible::<G>::reduce(keys, base, &d);
+    G::simul_exp(&[Q, base.clone()], &[d, r])
+}
+
+// product of the keys modulo p
+fn remainder(keys: &[Integer], p: &Integer) -> Integer {
+    let mut rem = Integer::from(1);
+    for key in keys.iter() {
+        rem = rem * key;
+        rem %= p;
+    }
+    rem
 }
 
 #[derive(Debug, Serialize)]
 pub struct TrivialProof();
 
 impl<G: ConvertibleUnknownOrderGroup> ProofOfExp<G> for TrivialProof {
-    fn new<I: Iterator<Item = Integer>>(base: &G::Elem, keys: I) -> Self {
+    fn new<I: Iterator<Item = Integer>>(
+        _params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Self {
         TrivialProof()
     }
 
-    fn verify<I: Iterator<Item = Integer>>(&self, base: &G::Elem, keys: I) -> Option<G::Elem> {
+    fn verify<I: Iterator<Item = Integer>>(
+        &self,
+        _params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Option<G::Elem> {
         let mut res = base.clone();
         for key in keys {
             res = G::exp(&res, &key);
@@ -35,6 +96,9 @@ impl<G: ConvertibleUnknownOrderGroup> ProofOfExp<G> for TrivialProof {
 }
 
 /// This proof is just a proof-of-knowledge (not zero-knowledge)
+///
+/// Note that the prime challenge does not depend on the result or `Q`,
+/// see `WesolowskiProof` for a proof bound to the result.
 #[derive(Debug, Serialize)]
 pub struct Proof<G: ConvertibleUnknownOrderGroup> {
     Q: G::Elem,
@@ -43,31 +107,49 @@ pub struct Proof<G: ConvertibleUnknownOrderGroup> {
 }
 
 impl<G: ConvertibleUnknownOrderGroup> ProofOfExp<G> for Proof<G> {
-    fn new<I: Iterator<Item = Integer>>(base: &G::Elem, keys: I) -> Self {
-        // compute exponent
-        let mut exp = Integer::from(1);
+    fn new<I: Iterator<Item = Integer>>(
+        params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Self {
         // commit to statement
         let mut transcript = Transcript::new(TRANSCRIPT_SEP);
         TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"base", base);
-        for key in keys {
-            exp = exp * &key;
-            TranscriptProtocolInteger::<G>::append_integer_scalar(&mut transcript, b"keys", &key);
-        }
+        let keys: Vec<Integer> = keys
+            .map(|key| {
+                TranscriptProtocolInteger::<G>::append_integer_scalar(
+                    &mut transcript,
+                    b"keys",
+                    &key,
+                );
+                key
+            })
+            .collect();
         let p = TranscriptProtocolChallenge::challenge_scalar(
             &mut transcript,
             b"prime",
-            STAT_SECURITY as u16,
+            params.prime_bits,
         )
         .next_prime();
-        let (q, r) = exp.div_rem(p.clone());
-        Self {
-            Q: G::exp(base, &q),
-            r,
-            p,
-        }
+
+        // compute base^(exp / p) without multiplying out the keys
+        let (Q, r) = Reducible::<G>::reduce(&keys, base, &p);
+        Self { Q, r, p }
     }
 
-    fn verify<I: Iterator<Item = Integer>>(&self, base: &G::Elem, keys: I) -> Option<G::Elem> {
+    fn verify<I: Iterator<Item = Integer>>(
+        &self,
+        params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Option<G::Elem> {
+        // the prime is a challenge of `prime_bits` and r a remainder modulo it
+        let bits = params.prime_bits as u32 + 1;
+        if !bounded(&self.p, bits) || !bounded(&self.r, bits) {
+            return None;
+        }
+        G::validate(&self.Q).ok()?;
+
         // commit to statement
         let mut rem = Integer::from(1);
         let mut transcript = Transcript::new(TRANSCRIPT_SEP);
@@ -83,15 +165,145 @@ impl<G: ConvertibleUnknownOrderGroup> ProofOfExp<G> for Proof<G> {
         let p = TranscriptProtocolChallenge::challenge_scalar(
             &mut transcript,
             b"prime",
-            STAT_SECURITY as u16,
+            params.prime_bits,
         )
         .next_prime();
         if self.p != p {
             return None;
         }
 
-        let Qp = G::exp(&self.Q, &p);
-        let Br = G::exp(base, &rem);
-        Some(G::op(&Qp, &Br))
+        // Q^p * base^r
+        Some(G::simul_exp(&[self.Q.clone(), base.clone()], &[p, rem]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::unknown::random_order;
+
+    use accumulator::group::{ClassGroup, Rsa2048, UnknownOrderGroup};
+
+    use rug::rand::RandState;
+
+    use test::Bencher;
+
+    fn test_backend<G: ConvertibleUnknownOrderGroup, E: ProofOfExp<G>>() {
+        let params = SecurityParameters::for_group::<G>();
+        let mut rand = RandState::new();
+        let base = G::unknown_possibly_random_order_elem(&mut rand);
+        let keys: Vec<Integer> = (0..20).map(|_| random_order::<G>(&params)).collect();
+
+        let expected = G::exp(&base, &exponent(keys.iter()));
+        let proof = E::new(&params, &base, keys.iter().cloned());
+        assert_eq!(
+            proof.verify(&params, &base, keys.iter().cloned()),
+            Some(expected.clone())
+        );
+
+        // proof must not verify for other keys
+        let mut other = keys.clone();
+        other[3] += 1;
+        assert_ne!(
+            proof.verify(&params, &base, other.iter().cloned()),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_oversized() {
+        let params = SecurityParameters::for_group::<Rsa2048>();
+        let mut rand = RandState::new();
+        let base = Rsa2048::unknown_possibly_random_order_elem(&mut rand);
+        let keys: Vec<Integer> = (0..20).map(|_| random_order::<Rsa2048>(&params)).collect();
+        let proof = <Proof<Rsa2048> as ProofOfExp<_>>::new(&params, &base, keys.iter().cloned());
+
+        let huge = Integer::from(1) << 4096;
+        let mut other = Proof::<Rsa2048> {
+            Q: proof.Q.clone(),
+            r: proof.r.clone(),
+            p: Integer::from(&proof.p + &huge),
+        };
+        assert!(other.verify(&params, &base, keys.iter().cloned()).is_none());
+
+        other.p = proof.p.clone();
+        other.r = Integer::from(&proof.r + &huge);
+        assert!(other.verify(&params, &base, keys.iter().cloned()).is_none());
+
+        other.r = proof.r;
+        assert!(other.verify(&params, &base, keys.iter().cloned()).is_some());
+    }
+
+    fn test_forged_quotient<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
+        let mut rand = RandState::new();
+        let base = G::unknown_possibly_random_order_elem(&mut rand);
+        let keys: Vec<Integer> = (0..20).map(|_| random_order::<G>(&params)).collect();
+        let expected = G::exp(&base, &exponent(keys.iter()));
+
+        // an unbound proof accepts any Q, only the comparison by the caller catches it
+        let proof = <Proof<G> as ProofOfExp<G>>::new(&params, &base, keys.iter().cloned());
+        let forged = Proof::<G> {
+            Q: G::op(&proof.Q, &base),
+            r: proof.r,
+            p: proof.p,
+        };
+        let res = forged.verify(&params, &base, keys.iter().cloned());
+        assert!(res.is_some());
+        assert_ne!(res, Some(expected));
+
+        // `WesolowskiProof` rejects a forged Q itself, see its tests
+    }
+
+    #[test]
+    fn test_forged_quotient_rsa() {
+        test_forged_quotient::<Rsa2048>();
+    }
+
+    #[test]
+    fn test_forged_quotient_classgroup() {
+        test_forged_quotient::<ClassGroup>();
+    }
+
+    #[test]
+    fn test_wesolowski() {
+        test_backend::<Rsa2048, WesolowskiProof<_>>();
+        test_backend::<ClassGroup, WesolowskiProof<_>>();
+    }
+
+    #[test]
+    fn test_pietrzak() {
+        test_backend::<Rsa2048, PietrzakProof<_>>();
+        test_backend::<ClassGroup, PietrzakProof<_>>();
+    }
+
+    #[test]
+    fn test_poke() {
+        test_backend::<Rsa2048, PokeProof<_>>();
+        test_backend::<ClassGroup, PokeProof<_>>();
+    }
+
+    fn bench_prove<E: ProofOfExp<Rsa2048>>(b: &mut Bencher, n: usize) {
+        let params = SecurityParameters::for_group::<Rsa2048>();
+        let mut rand = RandState::new();
+        let base = Rsa2048::unknown_possibly_random_order_elem(&mut rand);
+        let keys: Vec<Integer> = (0..n).map(|_| random_order::<Rsa2048>(&params)).collect();
+        b.iter(|| E::new(&params, &base, keys.iter().cloned()))
+    }
+
+    #[bench]
+    fn bench_prove_10k(b: &mut Bencher) {
+        bench_prove::<Proof<_>>(b, 10_000);
+    }
+
+    #[bench]
+    fn bench_prove_100k(b: &mut Bencher) {
+        bench_prove::<Proof<_>>(b, 100_000);
+    }
+
+    #[bench]
+    fn bench_prove_1m(b: &mut Bencher) {
+        bench_prove::<Proof<_>>(b, 1_000_000);
     }
 }
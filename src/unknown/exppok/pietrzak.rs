@@ -0,0 +1,243 @@
+use cpsnarks_set::transcript::{TranscriptProtocolChallenge, TranscriptProtocolInteger};
+use cpsnarks_set::utils::ConvertibleUnknownOrderGroup;
+
+use rug::Integer;
+
+use merlin::Transcript;
+
+use serde::Serialize;
+
+use super::{exponent, power, ProofOfExp};
+use crate::params::SecurityParameters;
+use crate::unknown::reduce::product;
+
+const TRANSCRIPT_SEP: &'static [u8] = b"ZK_POE_PIETRZAK";
+
+// exponents of at most this many bits are checked directly by the verifier
+fn direct_bits(params: &SecurityParameters) -> u32 {
+    4 * params.challenge_bits as u32
+}
+
+/// Proof of exponentiation based on the halving protocol of Pietrzak.
+///
+/// Every round splits the exponent e = e_hi * 2^t + e_lo, the prover sends
+/// mu = base^e_hi and nu = base^e_lo and proves mu^(2^t) = y / nu using Pietrzak,
+/// the two claims on the base are then merged into base^(c * e_hi + e_lo) = mu^c * nu.
+/// This roughly halves the exponent in each round.
+#[derive(Debug, Serialize)]
+pub struct PietrzakProof<G: ConvertibleUnknownOrderGroup> {
+    y: G::Elem, // claimed result
+    rounds: Vec<Round<G>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Round<G: ConvertibleUnknownOrderGroup> {
+    mu: G::Elem,            // base^e_hi
+    nu: G::Elem,            // base^e_lo
+    squaring: Vec<G::Elem>, // Pietrzak proof of mu^(2^t) = y / nu
+}
+
+fn challenge(params: &SecurityParameters, transcript: &mut Transcript) -> Integer {
+    TranscriptProtocolChallenge::challenge_scalar(transcript, b"challenge", params.challenge_bits)
+}
+
+fn split(e: &Integer) -> (u32, Integer, Integer) {
+    let t = e.significant_bits() / 2;
+    let e_hi = Integer::from(e >> t);
+    let e_lo = Integer::from(e.keep_bits_ref(t));
+    (t, e_hi, e_lo)
+}
+
+// Pietrzak proof that y = x^(2^t), returns the midpoints
+fn prove_squaring<G: ConvertibleUnknownOrderGroup>(
+    params: &SecurityParameters,
+    transcript: &mut Transcript,
+    mut x: G::Elem,
+    mut y: G::Elem,
+    mut t: u32,
+) -> Vec<G::Elem> {
+    let mut mus = vec![];
+    while t > 1 {
+        if t % 2 == 1 {
+            x = G::op(&x, &x);
+            t -= 1;
+        }
+        t /= 2;
+        let mu = G::exp(&x, &(Integer::from(1) << t));
+        TranscriptProtocolInteger::<G>::append_integer_point(transcript, b"mu", &mu);
+        let r = challenge(params, transcript);
+        x = G::op(&G::exp(&x, &r), &mu);
+        y = G::op(&G::exp(&mu, &r), &y);
+        mus.push(mu);
+    }
+    mus
+}
+
+fn verify_squaring<G: ConvertibleUnknownOrderGroup>(
+    params: &SecurityParameters,
+    transcript: &mut Transcript,
+    mut x: G::Elem,
+    mut y: G::Elem,
+    mut t: u32,
+    mus: &[G::Elem],
+) -> bool {
+    let mut mus = mus.iter();
+    while t > 1 {
+        if t % 2 == 1 {
+            x = G::op(&x, &x);
+            t -= 1;
+        }
+        t /= 2;
+        let mu = match mus.next() {
+            Some(mu) => mu,
+            None => return false,
+        };
+        TranscriptProtocolInteger::<G>::append_integer_point(transcript, b"mu", mu);
+        let r = challenge(params, transcript);
+        x = G::op(&G::exp(&x, &r), mu);
+        y = G::op(&G::exp(mu, &r), &y);
+    }
+    if mus.next().is_some() {
+        return false;
+    }
+    match t {
+        0 => x == y,
+        _ => G::op(&x, &x) == y,
+    }
+}
+
+fn statement<G: ConvertibleUnknownOrderGroup>(
+    base: &G::Elem,
+    exp: &Integer,
+    y: &G::Elem,
+) -> Transcript {
+    let mut transcript = Transcript::new(TRANSCRIPT_SEP);
+    TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"base", base);
+    TranscriptProtocolInteger::<G>::append_integer_scalar(&mut transcript, b"exp", exp);
+    TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"y", y);
+    transcript
+}
+
+impl<G: ConvertibleUnknownOrderGroup> ProofOfExp<G> for PietrzakProof<G> {
+    fn new<I: Iterator<Item = Integer>>(
+        params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Self {
+        let keys: Vec<Integer> = keys.collect();
+        let y = power::<G>(params, base, &keys);
+        let mut exp = product(&keys);
+        let mut transcript = statement::<G>(base, &exp, &y);
+
+        let mut rounds = vec![];
+        let mut claim = y.clone();
+        while exp.significant_bits() > direct_bits(params) {
+            let (t, e_hi, e_lo) = split(&exp);
+            let mu = G::exp(base, &e_hi);
+            let nu = G::exp(base, &e_lo);
+            TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"mu", &mu);
+            TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"nu", &nu);
+
+            // mu^(2^t) = y / nu
+            let rest = G::op(&claim, &G::inv(&nu));
+            let squaring = prove_squaring::<G>(params, &mut transcript, mu.clone(), rest, t);
+
+            // merge claims on the base
+            let c = challenge(params, &mut transcript);
+            claim = G::op(&G::exp(&mu, &c), &nu);
+            exp = c * e_hi + e_lo;
+            rounds.push(Round { mu, nu, squaring });
+        }
+
+        Self { y, rounds }
+    }
+
+    fn verify<I: Iterator<Item = Integer>>(
+        &self,
+        params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Option<G::Elem> {
+        G::validate(&self.y).ok()?;
+        for round in self.rounds.iter() {
+            G::validate(&round.mu).ok()?;
+            G::validate(&round.nu).ok()?;
+            for mu in round.squaring.iter() {
+                G::validate(mu).ok()?;
+            }
+        }
+
+        let mut exp = exponent(keys);
+        let mut transcript = statement::<G>(base, &exp, &self.y);
+
+        let mut rounds = self.rounds.iter();
+        let mut claim = self.y.clone();
+        while exp.significant_bits() > direct_bits(params) {
+            let round = rounds.next()?;
+            let (t, e_hi, e_lo) = split(&exp);
+            TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"mu", &round.mu);
+            TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"nu", &round.nu);
+
+            // mu^(2^t) = y / nu
+            let rest = G::op(&claim, &G::inv(&round.nu));
+            if !verify_squaring::<G>(
+                params,
+                &mut transcript,
+                round.mu.clone(),
+                rest,
+                t,
+                &round.squaring,
+            ) {
+                return None;
+            }
+
+            // merge claims on the base
+            let c = challenge(params, &mut transcript);
+            claim = G::op(&G::exp(&round.mu, &c), &round.nu);
+            exp = c * e_hi + e_lo;
+        }
+
+        // check remaining (short) exponent directly
+        if rounds.next().is_some() || G::exp(base, &exp) != claim {
+            return None;
+        }
+        Some(self.y.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::unknown::random_order;
+
+    use accumulator::group::{ElemFrom, Rsa2048, UnknownOrderGroup};
+
+    #[test]
+    fn test_invalid_elem() {
+        let params = SecurityParameters::for_group::<Rsa2048>();
+        let base = Rsa2048::unknown_order_elem();
+        let keys: Vec<Integer> = (0..4).map(|_| random_order::<Rsa2048>(&params)).collect();
+        let proof = PietrzakProof::<Rsa2048>::new(&params, &base, keys.iter().cloned());
+        assert!(proof.verify(&params, &base, keys.iter().cloned()).is_some());
+        assert!(!proof.rounds[0].squaring.is_empty());
+
+        // replaces a single element by zero, which is not canonical
+        let invalid = |i: usize| {
+            let mut proof = PietrzakProof::<Rsa2048>::new(&params, &base, keys.iter().cloned());
+            let zero = Rsa2048::elem(0);
+            match i {
+                0 => proof.y = zero,
+                1 => proof.rounds[0].mu = zero,
+                2 => proof.rounds[0].nu = zero,
+                _ => proof.rounds[0].squaring[0] = zero,
+            }
+            proof
+        };
+        for i in 0..4 {
+            assert!(invalid(i)
+                .verify(&params, &base, keys.iter().cloned())
+                .is_none());
+        }
+    }
+}
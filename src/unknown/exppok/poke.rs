@@ -0,0 +1,81 @@
+use accumulator::proof::Poke2;
+
+use cpsnarks_set::utils::ConvertibleUnknownOrderGroup;
+
+use rug::Integer;
+
+use serde::Serialize;
+
+use super::{power, ProofOfExp};
+use crate::params::SecurityParameters;
+use crate::unknown::reduce::product;
+
+/// BBF proof of knowledge of exponent (PoKE2),
+/// the residue of the exponent is checked against the product of the keys.
+#[derive(Debug, Serialize)]
+pub struct PokeProof<G: ConvertibleUnknownOrderGroup> {
+    y: G::Elem, // claimed result
+    proof: Poke2<G>,
+}
+
+impl<G: ConvertibleUnknownOrderGroup> ProofOfExp<G> for PokeProof<G> {
+    fn new<I: Iterator<Item = Integer>>(
+        params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Self {
+        let keys: Vec<Integer> = keys.collect();
+        let y = power::<G>(params, base, &keys);
+        let exp = product(&keys);
+        let proof = Poke2::prove(base, &exp, &y);
+        Self { y, proof }
+    }
+
+    fn verify<I: Iterator<Item = Integer>>(
+        &self,
+        _params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Option<G::Elem> {
+        G::validate(&self.y).ok()?;
+        self.proof.validate().ok()?;
+
+        // check that the proven exponent is the product of the keys (mod l)
+        let (l, r) = self.proof.residue(base, &self.y);
+        let mut rem = Integer::from(1);
+        for key in keys {
+            rem = rem * key;
+            rem %= &l;
+        }
+        if &rem != r {
+            return None;
+        }
+
+        if !Poke2::verify(base, &self.y, &self.proof) {
+            return None;
+        }
+        Some(self.y.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::unknown::random_order;
+
+    use accumulator::group::{ElemFrom, Rsa2048, UnknownOrderGroup};
+
+    #[test]
+    fn test_invalid_elem() {
+        let params = SecurityParameters::for_group::<Rsa2048>();
+        let base = Rsa2048::unknown_order_elem();
+        let keys: Vec<Integer> = (0..4).map(|_| random_order::<Rsa2048>(&params)).collect();
+        let mut proof = PokeProof::<Rsa2048>::new(&params, &base, keys.iter().cloned());
+        assert!(proof.verify(&params, &base, keys.iter().cloned()).is_some());
+
+        // zero is not a canonical element
+        proof.y = Rsa2048::elem(0);
+        assert!(proof.verify(&params, &base, keys.iter().cloned()).is_none());
+    }
+}
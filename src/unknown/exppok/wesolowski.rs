@@ -0,0 +1,127 @@
+use cpsnarks_set::transcript::{TranscriptProtocolChallenge, TranscriptProtocolInteger};
+use cpsnarks_set::utils::ConvertibleUnknownOrderGroup;
+
+use rug::Integer;
+
+use merlin::Transcript;
+
+use serde::Serialize;
+
+use super::{power, remainder, ProofOfExp};
+use crate::params::SecurityParameters;
+use crate::unknown::reduce::Reducible;
+
+const TRANSCRIPT_SEP: &'static [u8] = b"ZK_POE_WESOLOWSKI";
+
+/// Wesolowski proof of exponentiation,
+/// unlike `Proof` the prime challenge is bound to the claimed result.
+///
+/// The verifier checks `Q^p * base^(exp mod p) = y` for the result `y` it returns,
+/// so a forged `Q` is rejected by `verify` and not only by the comparison done by the caller.
+/// `Q` itself depends on `p` and needs no binding.
+#[derive(Debug, Serialize)]
+pub struct WesolowskiProof<G: ConvertibleUnknownOrderGroup> {
+    y: G::Elem, // claimed result
+    Q: G::Elem, // base^(exp / p)
+}
+
+fn challenge<G: ConvertibleUnknownOrderGroup>(
+    params: &SecurityParameters,
+    base: &G::Elem,
+    keys: &[Integer],
+    y: &G::Elem,
+) -> Integer {
+    let mut transcript = Transcript::new(TRANSCRIPT_SEP);
+    TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"base", base);
+    for key in keys.iter() {
+        TranscriptProtocolInteger::<G>::append_integer_scalar(&mut transcript, b"keys", key);
+    }
+    TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"y", y);
+    TranscriptProtocolChallenge::challenge_scalar(&mut transcript, b"prime", params.prime_bits)
+        .next_prime()
+}
+
+impl<G: ConvertibleUnknownOrderGroup> ProofOfExp<G> for WesolowskiProof<G> {
+    fn new<I: Iterator<Item = Integer>>(
+        params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Self {
+        let keys: Vec<Integer> = keys.collect();
+        let y = power::<G>(params, base, &keys);
+        let p = challenge::<G>(params, base, &keys, &y);
+        let (Q, _) = Reducible::<G>::reduce(&keys, base, &p);
+        Self { y, Q }
+    }
+
+    fn verify<I: Iterator<Item = Integer>>(
+        &self,
+        params: &SecurityParameters,
+        base: &G::Elem,
+        keys: I,
+    ) -> Option<G::Elem> {
+        G::validate(&self.y).ok()?;
+        G::validate(&self.Q).ok()?;
+
+        let keys: Vec<Integer> = keys.collect();
+        let p = challenge::<G>(params, base, &keys, &self.y);
+
+        let rem = remainder(&keys, &p);
+
+        // check Q^p * base^r = y
+        if G::simul_exp(&[self.Q.clone(), base.clone()], &[p, rem]) != self.y {
+            return None;
+        }
+        Some(self.y.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::unknown::random_order;
+
+    use accumulator::group::{ClassGroup, Rsa2048, UnknownOrderGroup};
+
+    use rug::rand::RandState;
+
+    fn test_forged_quotient<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
+        let mut rand = RandState::new();
+        let base = G::unknown_possibly_random_order_elem(&mut rand);
+        let keys: Vec<Integer> = (0..20).map(|_| random_order::<G>(&params)).collect();
+
+        let proof = WesolowskiProof::<G>::new(&params, &base, keys.iter().cloned());
+        assert!(proof.verify(&params, &base, keys.iter().cloned()).is_some());
+
+        // rejected without comparing the result to anything
+        let forged = WesolowskiProof::<G> {
+            y: proof.y.clone(),
+            Q: G::op(&proof.Q, &base),
+        };
+        assert!(forged
+            .verify(&params, &base, keys.iter().cloned())
+            .is_none());
+
+        // so is a result shifted along with Q: the prime changes with it
+        let p = challenge::<G>(&params, &base, &keys, &proof.y);
+        let forged = WesolowskiProof::<G> {
+            y: G::op(&proof.y, &G::exp(&base, &p)),
+            Q: G::op(&proof.Q, &base),
+        };
+        assert!(forged
+            .verify(&params, &base, keys.iter().cloned())
+            .is_none());
+    }
+
+    #[test]
+    fn test_forged_quotient_rsa() {
+        test_forged_quotient::<Rsa2048>();
+    }
+
+    #[test]
+    fn test_forged_quotient_classgroup() {
+        test_forged_quotient::<ClassGroup>();
+    }
+}
@@ -1,3 +1,4 @@
+use accumulator::group::multi_exp;
 use cpsnarks_set::commitments::pedersen::PedersenCommitment;
 use cpsnarks_set::utils::ConvertibleUnknownOrderGroup;
 
@@ -5,55 +6,390 @@ use rand_core::{OsRng, RngCore};
 use rug::Integer;
 
 use crate::bytes_to_integer;
-use crate::unknown::random_order;
+use crate::params::SecurityParameters;
+use crate::unknown::fixed::FixedBase;
+use crate::unknown::reduce::{product, product_iter};
+use crate::unknown::{par_all, par_map, random_order};
 
 use serde::{Deserialize, Serialize};
 
 #[allow(non_snake_case)]
 mod zkpokrep;
 
-#[allow(non_snake_case)]
-mod reduce;
-
 #[derive(Serialize, Debug)]
 pub struct ExtendProof<G: ConvertibleUnknownOrderGroup> {
     proof: zkpokrep::ZKPokRep<G>,
 }
 
+/// Extension steps with the quotients of all proofs aggregated into a single element
+#[allow(non_snake_case)]
+#[derive(Serialize, Debug)]
+pub struct AggregateExtendProof<G: ConvertibleUnknownOrderGroup> {
+    steps: Vec<zkpokrep::ZKPokRepRoot<G>>,
+    Q: G::Elem,
+}
+
+// witness for an extension step: com_new = com^(keys[0] * ... * keys[n-1]) * h^r_delta
+#[derive(Debug, Clone)]
+struct Opening {
+    keys: Vec<Integer>,
+    r_delta: Integer,
+}
+
+// consecutive steps with known openings (see ExtendChain::compact)
+struct Run<G: ConvertibleUnknownOrderGroup> {
+    start: G::Elem,                  // commitment before the run
+    opening: Opening,                // combined opening of the run
+    len: usize,                      // number of steps in the run
+    last: (G::Elem, ExtendProof<G>), // last step of the run
+}
+
+#[derive(Serialize, Debug)]
+enum ChainProof<G: ConvertibleUnknownOrderGroup> {
+    Steps(Vec<ExtendProof<G>>),
+    Aggregate(AggregateExtendProof<G>),
+}
+
+/// The chain of extensions of a signature
+#[derive(Serialize, Debug)]
+pub struct ExtendChain<G: ConvertibleUnknownOrderGroup> {
+    coms: Vec<G::Elem>, // new commitment of every step
+    proof: ChainProof<G>,
+
+    // openings of the steps done by this party (never serialized)
+    #[serde(skip)]
+    openings: Vec<Option<Opening>>,
+}
+
 impl<G: ConvertibleUnknownOrderGroup> ExtendProof<G> {
     pub fn new(
+        params: &SecurityParameters,
         com: &G::Elem,    // Fujisaki-Okamoto commitment
-        h: &G::Elem,      // randomness generator
+        h: &FixedBase<G>, // randomness generator
         r: Integer,       // randomness scalar
         keys: &[Integer], // keys to add
     ) -> (G::Elem, Integer, ExtendProof<G>) {
+        let (com_new, r_new, proof, _) = Self::new_with_opening(params, com, h, r, keys.to_vec());
+        (com_new, r_new, proof)
+    }
+
+    /// Like `new`, but consumes the keys one at a time (e.g. from a `RingReader`)
+    pub fn new_iter<I: Iterator<Item = Integer>>(
+        params: &SecurityParameters,
+        com: &G::Elem,    // Fujisaki-Okamoto commitment
+        h: &FixedBase<G>, // randomness generator
+        r: Integer,       // randomness scalar
+        keys: I,          // keys to add
+    ) -> (G::Elem, Integer, ExtendProof<G>) {
+        let (com_new, r_new, proof, _) =
+            Self::new_with_opening(params, com, h, r, vec![product_iter(keys)]);
+        (com_new, r_new, proof)
+    }
+
+    fn new_with_opening(
+        params: &SecurityParameters,
+        com: &G::Elem,      // Fujisaki-Okamoto commitment
+        h: &FixedBase<G>,   // randomness generator
+        r: Integer,         // randomness scalar
+        keys: Vec<Integer>, // keys to add (or any factorization of their product)
+    ) -> (G::Elem, Integer, ExtendProof<G>, Opening) {
         // compute exponent
-        let mut mul = Integer::from(1);
-        for v in keys.iter() {
-            mul = mul * v;
-        }
+        let mul = product(&keys);
 
         // pick new randomness
-        let r_new = random_order::<G>();
+        let r_new = random_order::<G>(params);
         let r_delta = (&r_new - &mul * &r).into();
 
+        let opening = Opening { keys, r_delta };
+        let (com_new, proof) = Self::prove(params, com, h, &opening);
+        (com_new, r_new, proof, opening)
+    }
+
+    fn prove(
+        params: &SecurityParameters,
+        com: &G::Elem,    // Fujisaki-Okamoto commitment
+        h: &FixedBase<G>, // randomness generator
+        opening: &Opening,
+    ) -> (G::Elem, ExtendProof<G>) {
         // compute new commitment
-        let h_rnd = G::exp(h, &r_delta);
-        let com_mul = G::exp(com, &mul);
+        let h_rnd = h.exp_secret(&opening.r_delta);
+        let com_mul = G::exp(com, &product(&opening.keys)); // keys are public
         let com_new = G::op(&h_rnd, &com_mul);
 
-        //
-        let proof = zkpokrep::ZKPokRep::new(com, h, &com_new, &mul, &r_delta);
-        (com_new, r_new, ExtendProof { proof })
+        // prove without multiplying out the keys (see reduce.rs)
+        let proof = zkpokrep::ZKPokRep::new_factors(
+            params,
+            com,
+            h.base(),
+            &com_new,
+            opening.keys.clone(),
+            &opening.r_delta,
+        );
+        (com_new, ExtendProof { proof })
     }
 
     pub fn verify(
         &self,
+        params: &SecurityParameters,
         com: &G::Elem,     // Fujisaki-Okamoto commitment
         h: &G::Elem,       // randomness generator
         com_new: &G::Elem, // new commitment
     ) -> bool {
-        self.proof.verify(com, h, com_new)
+        self.proof.verify(params, com, h, com_new)
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> AggregateExtendProof<G> {
+    fn new() -> Self {
+        AggregateExtendProof {
+            steps: vec![],
+            Q: G::id(),
+        }
+    }
+
+    #[allow(non_snake_case)]
+    fn push(&mut self, proof: ExtendProof<G>) {
+        let (step, Q) = proof.proof.split();
+        self.steps.push(step);
+        self.Q = G::op(&self.Q, &Q);
+    }
+
+    pub fn verify(
+        &self,
+        params: &SecurityParameters,
+        com: &G::Elem,    // initial Fujisaki-Okamoto commitment
+        h: &G::Elem,      // randomness generator
+        coms: &[G::Elem], // new commitment of every step
+    ) -> bool {
+        if coms.len() != self.steps.len() || G::validate(&self.Q).is_err() {
+            return false;
+        }
+
+        // recover the root statement of every step
+        let statements = par_map(coms.len(), |i| {
+            let last = if i == 0 { com } else { &coms[i - 1] };
+            self.steps[i].root(params, last, h, &coms[i])
+        });
+
+        let mut roots: Vec<G::Elem> = Vec::with_capacity(coms.len());
+        let mut primes: Vec<Integer> = Vec::with_capacity(coms.len());
+        for statement in statements {
+            let (root, p) = match statement {
+                Some(statement) => statement,
+                None => return false,
+            };
+
+            // primes must be distinct for the aggregation to be sound
+            if primes.contains(&p) {
+                return false;
+            }
+
+            roots.push(root);
+            primes.push(p);
+        }
+
+        if roots.is_empty() {
+            return self.Q == G::id();
+        }
+
+        // Q^(p_1 ... p_n) = prod_i T_i^(p_1 ... p_n / p_i)
+        let lhs = G::exp(&self.Q, &primes.iter().product());
+        lhs == multi_exp::<G>(&roots[..], &primes[..])
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> Default for ExtendChain<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<G: ConvertibleUnknownOrderGroup> ExtendChain<G> {
+    pub fn new() -> Self {
+        ExtendChain {
+            coms: vec![],
+            proof: ChainProof::Steps(vec![]),
+            openings: vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.coms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coms.is_empty()
+    }
+
+    /// Returns the last commitment in the chain (if any)
+    pub fn last(&self) -> Option<&G::Elem> {
+        self.coms.last()
+    }
+
+    pub fn is_aggregate(&self) -> bool {
+        match self.proof {
+            ChainProof::Steps(_) => false,
+            ChainProof::Aggregate(_) => true,
+        }
+    }
+
+    /// Appends a step created by another party
+    pub fn push(&mut self, com_new: G::Elem, proof: ExtendProof<G>) {
+        self.push_step(com_new, proof, None)
+    }
+
+    fn push_step(&mut self, com_new: G::Elem, proof: ExtendProof<G>, opening: Option<Opening>) {
+        match &mut self.proof {
+            ChainProof::Steps(proofs) => proofs.push(proof),
+            ChainProof::Aggregate(agg) => agg.push(proof),
+        }
+        self.coms.push(com_new);
+        self.openings.push(opening);
+    }
+
+    /// Extends the chain with the keys, returns the new randomness
+    pub fn extend(
+        &mut self,
+        params: &SecurityParameters,
+        com: &G::Elem,    // initial Fujisaki-Okamoto commitment
+        h: &FixedBase<G>, // randomness generator
+        r: Integer,       // randomness of the last commitment
+        keys: &[Integer], // keys to add
+    ) -> Integer {
+        self.extend_factors(params, com, h, r, keys.to_vec())
+    }
+
+    /// Like `extend`, but consumes the keys one at a time
+    pub fn extend_iter<I: Iterator<Item = Integer>>(
+        &mut self,
+        params: &SecurityParameters,
+        com: &G::Elem,    // initial Fujisaki-Okamoto commitment
+        h: &FixedBase<G>, // randomness generator
+        r: Integer,       // randomness of the last commitment
+        keys: I,          // keys to add
+    ) -> Integer {
+        self.extend_factors(params, com, h, r, vec![product_iter(keys)])
+    }
+
+    fn extend_factors(
+        &mut self,
+        params: &SecurityParameters,
+        com: &G::Elem,
+        h: &FixedBase<G>,
+        r: Integer,
+        keys: Vec<Integer>,
+    ) -> Integer {
+        let last = self.last().unwrap_or(com).clone();
+        let (com_new, r_new, proof, opening) =
+            ExtendProof::new_with_opening(params, &last, h, r, keys);
+        self.push_step(com_new, proof, Some(opening));
+        r_new
+    }
+
+    /// Replaces every run of (two or more) consecutive steps with known openings
+    /// by a single step from the first to the last commitment of the run.
+    ///
+    /// Only applies to chains which are not aggregated.
+    pub fn compact(&mut self, params: &SecurityParameters, com: &G::Elem, h: &FixedBase<G>) {
+        let proofs = match &mut self.proof {
+            ChainProof::Steps(proofs) => std::mem::replace(proofs, vec![]),
+            ChainProof::Aggregate(_) => return,
+        };
+
+        let coms = std::mem::replace(&mut self.coms, vec![]);
+        let openings = std::mem::replace(&mut self.openings, vec![]);
+
+        let mut run: Option<Run<G>> = None;
+        let mut prev = com.clone();
+
+        for ((com_new, proof), opening) in coms.into_iter().zip(proofs).zip(openings) {
+            run = match (run, opening) {
+                (None, Some(opening)) => Some(Run {
+                    start: prev,
+                    opening,
+                    len: 1,
+                    last: (com_new.clone(), proof),
+                }),
+                (Some(run), Some(opening)) => {
+                    // com_new = (start^mul_run h^r_run)^mul h^r_delta
+                    let r_delta = run.opening.r_delta * product(&opening.keys) + opening.r_delta;
+                    let mut keys = run.opening.keys;
+                    keys.extend(opening.keys);
+                    Some(Run {
+                        start: run.start,
+                        opening: Opening { keys, r_delta },
+                        len: run.len + 1,
+                        last: (com_new.clone(), proof),
+                    })
+                }
+                (run, None) => {
+                    if let Some(run) = run {
+                        self.close_run(params, h, run);
+                    }
+                    self.push_step(com_new.clone(), proof, None);
+                    None
+                }
+            };
+            prev = com_new;
+        }
+
+        if let Some(run) = run {
+            self.close_run(params, h, run);
+        }
+    }
+
+    fn close_run(&mut self, params: &SecurityParameters, h: &FixedBase<G>, run: Run<G>) {
+        let (com_new, proof) = run.last;
+
+        // a single step is kept as is
+        if run.len == 1 {
+            self.push_step(com_new, proof, Some(run.opening));
+            return;
+        }
+
+        let (com_merged, proof) = ExtendProof::prove(params, &run.start, h, &run.opening);
+        debug_assert_eq!(com_merged, com_new);
+        self.push_step(com_merged, proof, Some(run.opening));
+    }
+
+    /// Aggregates the quotients of all steps into a single group element,
+    /// this requires no openings, so any party can aggregate the chain.
+    pub fn aggregate(&mut self) {
+        let proofs = match &mut self.proof {
+            ChainProof::Steps(proofs) => std::mem::replace(proofs, vec![]),
+            ChainProof::Aggregate(_) => return,
+        };
+        let mut agg = AggregateExtendProof::new();
+        for proof in proofs {
+            agg.push(proof);
+        }
+        self.proof = ChainProof::Aggregate(agg);
+    }
+
+    pub fn verify(
+        &self,
+        params: &SecurityParameters,
+        com: &G::Elem, // initial Fujisaki-Okamoto commitment
+        h: &G::Elem,   // randomness generator
+    ) -> bool {
+        if self.coms.iter().any(|com| G::validate(com).is_err()) {
+            return false;
+        }
+
+        match &self.proof {
+            ChainProof::Steps(proofs) => {
+                if self.coms.len() != proofs.len() {
+                    return false;
+                }
+
+                // steps are independent
+                par_all(proofs.len(), |i| {
+                    let last_comm = if i == 0 { com } else { &self.coms[i - 1] };
+                    proofs[i].verify(params, last_comm, h, &self.coms[i])
+                })
+            }
+            ChainProof::Aggregate(agg) => agg.verify(params, com, h, &self.coms[..]),
+        }
     }
 }
 
@@ -90,26 +426,30 @@ mod tests {
     }
 
     fn test_extend<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
         let mut rand = RandState::new();
         let g = G::unknown_possibly_random_order_elem(&mut rand);
         let mut h = G::unknown_possibly_random_order_elem(&mut rand);
         if g == h {
-            let r = random_order::<G>();
+            let r = random_order::<G>(&params);
             h = G::exp(&h, &r);
         }
+        let h_fixed = FixedBase::<G>::with_order_bound(&h);
 
         let key_0 = random_integer(32);
         let keys_1 = random_integers(20, 32);
         let keys_2 = random_integers(20, 32);
 
-        let rnd0 = random_order::<G>();
+        let rnd0 = random_order::<G>(&params);
         let com0 = commit::<G>(&g, &h, &key_0, &rnd0);
 
-        let (com1, rnd1, proof0) = ExtendProof::<G>::new(&com0, &h, rnd0, &keys_1[..]);
-        let (com2, rnd2, proof1) = ExtendProof::<G>::new(&com1, &h, rnd1, &keys_2[..]);
+        let (com1, rnd1, proof0) =
+            ExtendProof::<G>::new(&params, &com0, &h_fixed, rnd0, &keys_1[..]);
+        let (com2, rnd2, proof1) =
+            ExtendProof::<G>::new(&params, &com1, &h_fixed, rnd1, &keys_2[..]);
 
-        assert!(proof0.verify(&com0, &h, &com1));
-        assert!(proof1.verify(&com1, &h, &com2));
+        assert!(proof0.verify(&params, &com0, &h, &com1));
+        assert!(proof1.verify(&params, &com1, &h, &com2));
 
         let mut product = key_0.clone();
         for k in keys_1.iter() {
@@ -122,11 +462,145 @@ mod tests {
         assert_eq!(commit::<G>(&g, &h, &product, &rnd2), com2);
     }
 
+    fn test_compact<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
+        let mut rand = RandState::new();
+        let g = G::unknown_possibly_random_order_elem(&mut rand);
+        let h = G::exp(&g, &random_order::<G>(&params));
+        let h_fixed = FixedBase::<G>::with_order_bound(&h);
+
+        let key_0 = random_integer(32);
+        let keys_1 = random_integers(5, 32);
+        let keys_2 = random_integers(5, 32);
+        let keys_3 = random_integers(5, 32);
+
+        let rnd0 = random_order::<G>(&params);
+        let com0 = commit::<G>(&g, &h, &key_0, &rnd0);
+
+        let mut chain = ExtendChain::<G>::new();
+        let rnd1 = chain.extend(&params, &com0, &h_fixed, rnd0, &keys_1[..]);
+        let rnd2 = chain.extend(&params, &com0, &h_fixed, rnd1, &keys_2[..]);
+
+        // step by another extender
+        let (com3, rnd3, proof) =
+            ExtendProof::<G>::new(&params, chain.last().unwrap(), &h_fixed, rnd2, &keys_3);
+        chain.push(com3.clone(), proof);
+
+        assert!(chain.verify(&params, &com0, &h));
+        assert_eq!(chain.len(), 3);
+
+        chain.compact(&params, &com0, &h_fixed);
+        assert!(chain.verify(&params, &com0, &h));
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain.last(), Some(&com3));
+
+        let mut product = key_0.clone();
+        for k in keys_1.iter().chain(keys_2.iter()).chain(keys_3.iter()) {
+            product = product * k;
+        }
+        assert_eq!(commit::<G>(&g, &h, &product, &rnd3), com3);
+    }
+
+    fn test_aggregate<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
+        let mut rand = RandState::new();
+        let g = G::unknown_possibly_random_order_elem(&mut rand);
+        let h = G::exp(&g, &random_order::<G>(&params));
+        let h_fixed = FixedBase::<G>::with_order_bound(&h);
+
+        let key_0 = random_integer(32);
+        let rnd0 = random_order::<G>(&params);
+        let com0 = commit::<G>(&g, &h, &key_0, &rnd0);
+
+        let mut chain = ExtendChain::<G>::new();
+        let mut rnd = rnd0;
+        for _ in 0..3 {
+            let keys = random_integers(5, 32);
+            let (com, r, proof) =
+                ExtendProof::<G>::new(&params, chain.last().unwrap_or(&com0), &h_fixed, rnd, &keys);
+            chain.push(com, proof);
+            rnd = r;
+        }
+
+        chain.aggregate();
+        assert!(chain.is_aggregate());
+        assert!(chain.verify(&params, &com0, &h));
+
+        // extending an aggregated chain keeps it aggregated
+        let keys = random_integers(5, 32);
+        chain.extend(&params, &com0, &h_fixed, rnd, &keys[..]);
+        assert!(chain.is_aggregate());
+        assert!(chain.verify(&params, &com0, &h));
+
+        // tampering with an intermediate commitment is detected
+        chain.coms[1] = G::op(&chain.coms[1], &h);
+        assert!(!chain.verify(&params, &com0, &h));
+    }
+
+    fn test_extend_iter<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
+        let mut rand = RandState::new();
+        let g = G::unknown_possibly_random_order_elem(&mut rand);
+        let h = G::exp(&g, &random_order::<G>(&params));
+        let h_fixed = FixedBase::<G>::with_order_bound(&h);
+
+        let key_0 = random_integer(32);
+        let keys_1 = random_integers(20, 32);
+        let keys_2 = random_integers(20, 32);
+
+        let rnd0 = random_order::<G>(&params);
+        let com0 = commit::<G>(&g, &h, &key_0, &rnd0);
+
+        let mut chain = ExtendChain::<G>::new();
+        let rnd1 = chain.extend_iter(&params, &com0, &h_fixed, rnd0, keys_1.iter().cloned());
+        let rnd2 = chain.extend(&params, &com0, &h_fixed, rnd1, &keys_2[..]);
+        assert!(chain.verify(&params, &com0, &h));
+
+        let product = product_iter(
+            keys_1
+                .iter()
+                .chain(keys_2.iter())
+                .cloned()
+                .chain(std::iter::once(key_0)),
+        );
+        assert_eq!(chain.last(), Some(&commit::<G>(&g, &h, &product, &rnd2)));
+
+        // steps with streamed keys can be compacted
+        chain.compact(&params, &com0, &h_fixed);
+        assert_eq!(chain.len(), 1);
+        assert!(chain.verify(&params, &com0, &h));
+    }
+
     #[test]
     fn test_extend_rsa() {
         test_extend::<Rsa2048>();
     }
 
+    #[test]
+    fn test_extend_iter_rsa() {
+        test_extend_iter::<Rsa2048>();
+    }
+
+    #[test]
+    fn test_compact_rsa() {
+        test_compact::<Rsa2048>();
+    }
+
+    #[test]
+    fn test_compact_classgroup() {
+        test_compact::<ClassGroup>();
+    }
+
+    #[test]
+    fn test_aggregate_rsa() {
+        test_aggregate::<Rsa2048>();
+    }
+
+    #[test]
+    fn test_aggregate_classgroup() {
+        test_aggregate::<ClassGroup>();
+    }
+
     #[test]
     fn test_extend_classgroup() {
         test_extend::<ClassGroup>();
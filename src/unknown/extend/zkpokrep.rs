@@ -8,12 +8,16 @@ use merlin::Transcript;
 
 use rug_binserial::Integer as BinInteger;
 
-use super::random_order;
+use std::cmp::Ordering;
 
-const TRANSCRIPT_SEP: &'static [u8] = b"ZK_POK_REP";
+use crate::params::SecurityParameters;
+use crate::unknown::reduce::{add_reducible, mul_reducible, Reducible};
+use crate::unknown::{join, order_bits, random_bits};
 
-const STAT_SECURITY: usize = 128;
+const TRANSCRIPT_SEP: &'static [u8] = b"ZK_POK_REP";
 
+/// Proof of knowledge of a representation y = g1^x1 g2^x2 which hides x2 only:
+/// in extensions x1 is the product of the (public) new keys and x2 the secret randomness.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ZKPokRep<G: ConvertibleUnknownOrderGroup> {
     a: G::Elem,
@@ -22,51 +26,107 @@ pub struct ZKPokRep<G: ConvertibleUnknownOrderGroup> {
     r2: BinInteger,
 }
 
+/// ZKPokRep without the quotient Q.
+/// Instead the verifier recovers the statement "Q is a p-th root of T",
+/// for distinct primes p these statements can be aggregated (PoKCR, see BBF page 11).
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ZKPokRepRoot<G: ConvertibleUnknownOrderGroup> {
+    a: G::Elem,
+    r1: BinInteger,
+    r2: BinInteger,
+}
+
+// derive challenge and prime from the statement and the first round message
+fn challenge<G: ConvertibleUnknownOrderGroup>(
+    params: &SecurityParameters,
+    g1: &G::Elem,
+    g2: &G::Elem,
+    y: &G::Elem,
+    a: &G::Elem,
+) -> (Integer, Integer) {
+    let mut transcript = Transcript::new(TRANSCRIPT_SEP);
+
+    TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"a", a);
+    TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"y", y);
+    TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"g1", g1);
+    TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"g2", g2);
+
+    let c = TranscriptProtocolChallenge::challenge_scalar(
+        &mut transcript,
+        b"challenge",
+        params.challenge_bits,
+    );
+
+    let p =
+        TranscriptProtocolChallenge::challenge_scalar(&mut transcript, b"prime", params.prime_bits)
+            .next_prime();
+
+    (c, p)
+}
+
+// size of the masks for a witness of the given size:
+// |c x| < 2^(witness + challenge), the masks add zk_bits of statistical slack on top,
+// such that c x + mask is 2^-zk_bits close to a mask alone (which a simulator can sample).
+// Witnesses are padded to the order bound, so only their size beyond it is revealed.
+fn mask_bits<G: ConvertibleUnknownOrderGroup>(
+    params: &SecurityParameters,
+    witness_bits: u32,
+) -> u32 {
+    witness_bits.max(order_bits::<G>()) + params.challenge_bits as u32 + params.zk_bits as u32
+}
+
+// mask of the secret witness x, the response is c x + mask
+fn mask<G: ConvertibleUnknownOrderGroup>(params: &SecurityParameters, x: &Integer) -> Integer {
+    random_bits(mask_bits::<G>(params, x.significant_bits()))
+}
+
+// honest responses are remainders modulo p, anything larger only slows down the verifier
+fn reduced(r: &BinInteger, p: &Integer) -> bool {
+    r.as_ref().cmp_abs(p) == Ordering::Less
+}
+
 impl<G: ConvertibleUnknownOrderGroup> ZKPokRep<G> {
     pub fn new(
+        params: &SecurityParameters,
         g1: &G::Elem, // generator 1
         g2: &G::Elem, // generator 2
         y: &G::Elem,  // y = g1^x1 g2^x2
         x1: &Integer, // dlog 1
         x2: &Integer, // dlog 2
     ) -> Self {
-        // first round message
-        let a_g1 = random_order::<G>();
-        let a_g2 = random_order::<G>();
-        let a = G::op(&G::exp(g1, &a_g1), &G::exp(g2, &a_g2));
-
-        // compute challenge
-
-        let mut transcript = Transcript::new(TRANSCRIPT_SEP);
+        Self::new_factors(params, g1, g2, y, vec![x1.clone()], x2)
+    }
 
-        TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"a", &a);
-        TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"y", &y);
-        TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"g1", &g1);
-        TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"g2", &g2);
+    /// Like `new`, but with dlog 1 given as a product of (small) factors,
+    /// the quotient is computed without multiplying the factors into a single integer.
+    pub fn new_factors(
+        params: &SecurityParameters,
+        g1: &G::Elem,     // generator 1
+        g2: &G::Elem,     // generator 2
+        y: &G::Elem,      // y = g1^x1 g2^x2
+        x1: Vec<Integer>, // factors of dlog 1
+        x2: &Integer,     // dlog 2
+    ) -> Self {
+        // first round message,
+        // dlog 1 is public (and possibly large), its mask is of the order size and only blinds g2
+        let a_g1 = random_bits(order_bits::<G>());
+        let a_g2 = mask::<G>(params, x2);
+        let a = G::op(&G::exp(g1, &a_g1), &G::exp_secret(g2, &a_g2));
 
-        let c = TranscriptProtocolChallenge::challenge_scalar(
-            &mut transcript,
-            b"challenge",
-            STAT_SECURITY as u16,
-        );
+        // compute challenge
 
-        let p = TranscriptProtocolChallenge::challenge_scalar(
-            &mut transcript,
-            b"prime",
-            STAT_SECURITY as u16,
-        )
-        .next_prime();
+        let (c, p) = challenge::<G>(params, g1, g2, y, &a);
 
         // compute response
 
-        let z1 = &c * x1 + a_g1;
+        let z1: Box<dyn Reducible<G>> =
+            add_reducible::<G>(mul_reducible::<G>(Box::new(x1), c.clone()), Box::new(a_g1));
         let z2 = &c * x2 + a_g2;
 
-        let (q1, r1) = z1.div_rem(p.clone());
+        let (Q1, r1) = z1.reduce(g1, &p);
         let (q2, r2) = z2.div_rem(p);
 
-        let Q1 = G::exp(g1, &q1);
-        let Q2 = G::exp(g2, &q2);
+        let Q2 = G::exp_secret(g2, &q2);
         let Q = G::op(&Q1, &Q2);
 
         Self {
@@ -79,42 +139,81 @@ impl<G: ConvertibleUnknownOrderGroup> ZKPokRep<G> {
 
     pub fn verify(
         &self,
+        params: &SecurityParameters,
         g1: &G::Elem, // generator 1
         g2: &G::Elem, // generator 2
         y: &G::Elem,  // y = g1^x1 g2^x2
     ) -> bool {
+        if G::validate(&self.a).is_err() || G::validate(&self.Q).is_err() {
+            return false;
+        }
+
         // compute challenge
-        let mut transcript = Transcript::new(TRANSCRIPT_SEP);
+        let (c, p) = challenge::<G>(params, g1, g2, y, &self.a);
+        if !reduced(&self.r1, &p) || !reduced(&self.r2, &p) {
+            return false;
+        }
 
-        TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"a", &self.a);
-        TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"y", &y);
-        TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"g1", &g1);
-        TranscriptProtocolInteger::<G>::append_integer_point(&mut transcript, b"g2", &g2);
+        // verify response
 
-        let c = TranscriptProtocolChallenge::challenge_scalar(
-            &mut transcript,
-            b"challenge",
-            STAT_SECURITY as u16,
+        // y^c a = Q^p g1^r1 g2^r2
+        let (left, right) = join(
+            || G::op(&G::exp(y, &c), &self.a),
+            || {
+                G::simul_exp(
+                    &[self.Q.clone(), g1.clone(), g2.clone()],
+                    &[p, self.r1.as_ref().clone(), self.r2.as_ref().clone()],
+                )
+            },
         );
 
-        let p = TranscriptProtocolChallenge::challenge_scalar(
-            &mut transcript,
-            b"prime",
-            STAT_SECURITY as u16,
+        left == right
+    }
+
+    /// Separates the quotient from the rest of the proof
+    pub fn split(self) -> (ZKPokRepRoot<G>, G::Elem) {
+        (
+            ZKPokRepRoot {
+                a: self.a,
+                r1: self.r1,
+                r2: self.r2,
+            },
+            self.Q,
         )
-        .next_prime();
+    }
+}
 
-        // verify response
+impl<G: ConvertibleUnknownOrderGroup> ZKPokRepRoot<G> {
+    /// Returns (T, p) such that the proof is valid iff. Q^p = T,
+    /// or None if the responses are out of range
+    pub fn root(
+        &self,
+        params: &SecurityParameters,
+        g1: &G::Elem, // generator 1
+        g2: &G::Elem, // generator 2
+        y: &G::Elem,  // y = g1^x1 g2^x2
+    ) -> Option<(G::Elem, Integer)> {
+        G::validate(&self.a).ok()?;
+
+        let (c, p) = challenge::<G>(params, g1, g2, y, &self.a);
+        if !reduced(&self.r1, &p) || !reduced(&self.r2, &p) {
+            return None;
+        }
 
-        let yc = G::exp(y, &c);
-        let Qp = G::exp(&self.Q, &p);
-        let g1r1 = G::exp(g1, self.r1.as_ref());
-        let g2r2 = G::exp(g2, self.r2.as_ref());
+        // T = y^c a / (g1^r1 g2^r2)
+        let (yc, right) = join(
+            || G::exp(y, &c),
+            || {
+                G::simul_exp(
+                    &[g1.clone(), g2.clone()],
+                    &[self.r1.as_ref().clone(), self.r2.as_ref().clone()],
+                )
+            },
+        );
 
-        let right = G::op(&Qp, &G::op(&g1r1, &g2r2));
         let left = G::op(&yc, &self.a);
 
-        left == right
+        Some((G::op(&left, &G::inv(&right)), p))
     }
 }
 
@@ -122,33 +221,36 @@ impl<G: ConvertibleUnknownOrderGroup> ZKPokRep<G> {
 mod tests {
     use super::*;
 
+    use crate::unknown::random_order;
+
     use rug::rand::RandState;
 
-    use accumulator::group::{ClassGroup, Rsa2048};
+    use accumulator::group::{ClassGroup, Group, Rsa2048, UnknownOrderGroup};
 
     use test::Bencher;
 
     use bincode;
 
     fn test_ser<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
         let mut rand = RandState::new();
         let g1 = G::unknown_possibly_random_order_elem(&mut rand);
         let g2 = G::unknown_possibly_random_order_elem(&mut rand);
 
-        let x1 = random_order::<G>();
-        let x2 = random_order::<G>();
+        let x1 = random_order::<G>(&params);
+        let x2 = random_order::<G>(&params);
 
         let y1 = G::exp(&g1, &x1);
         let y2 = G::exp(&g2, &x2);
         let y = G::op(&y1, &y2);
 
-        let pi = ZKPokRep::<G>::new(&g1, &g2, &y, &x1, &x2);
+        let pi = ZKPokRep::<G>::new(&params, &g1, &g2, &y, &x1, &x2);
 
         let bs = bincode::serialize(&pi).unwrap();
 
         println!("{:?} {}", &bs[..], bs.len());
 
-        assert!(pi.verify(&g1, &g2, &y));
+        assert!(pi.verify(&params, &g1, &g2, &y));
     }
 
     #[test]
@@ -162,59 +264,227 @@ mod tests {
     }
 
     fn test_mult_proof<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
         let mut rand = RandState::new();
         let g1 = G::unknown_possibly_random_order_elem(&mut rand);
         let g2 = G::unknown_possibly_random_order_elem(&mut rand);
 
-        let x1 = random_order::<G>();
-        let x2 = random_order::<G>();
+        let x1 = random_order::<G>(&params);
+        let x2 = random_order::<G>(&params);
 
         let y1 = G::exp(&g1, &x1);
         let y2 = G::exp(&g2, &x2);
         let y = G::op(&y1, &y2);
 
-        let pi = ZKPokRep::<G>::new(&g1, &g2, &y, &x1, &x2);
+        let pi = ZKPokRep::<G>::new(&params, &g1, &g2, &y, &x1, &x2);
 
-        assert!(pi.verify(&g1, &g2, &y));
+        assert!(pi.verify(&params, &g1, &g2, &y));
     }
 
     fn bench_proof_gen<G: ConvertibleUnknownOrderGroup>(b: &mut Bencher) {
         b.iter(|| {
+            let params = SecurityParameters::for_group::<G>();
             let mut rand = RandState::new();
             let g1 = G::unknown_possibly_random_order_elem(&mut rand);
             let g2 = G::unknown_possibly_random_order_elem(&mut rand);
 
-            let x1 = random_order::<G>();
-            let x2 = random_order::<G>();
+            let x1 = random_order::<G>(&params);
+            let x2 = random_order::<G>(&params);
 
             let y1 = G::exp(&g1, &x1);
             let y2 = G::exp(&g2, &x2);
             let y = G::op(&y1, &y2);
 
-            let _ = ZKPokRep::<G>::new(&g1, &g2, &y, &x1, &x2);
+            let _ = ZKPokRep::<G>::new(&params, &g1, &g2, &y, &x1, &x2);
         });
     }
 
     fn bench_proof_vrfy<G: ConvertibleUnknownOrderGroup>(b: &mut Bencher) {
+        let params = SecurityParameters::for_group::<G>();
         let mut rand = RandState::new();
         let g1 = G::unknown_possibly_random_order_elem(&mut rand);
         let g2 = G::unknown_possibly_random_order_elem(&mut rand);
 
-        let x1 = random_order::<G>();
-        let x2 = random_order::<G>();
+        let x1 = random_order::<G>(&params);
+        let x2 = random_order::<G>(&params);
 
         let y1 = G::exp(&g1, &x1);
         let y2 = G::exp(&g2, &x2);
         let y = G::op(&y1, &y2);
 
-        let pi = ZKPokRep::<G>::new(&g1, &g2, &y, &x1, &x2);
+        let pi = ZKPokRep::<G>::new(&params, &g1, &g2, &y, &x1, &x2);
         b.iter(|| {
-            assert!(pi.verify(&g1, &g2, &y));
+            assert!(pi.verify(&params, &g1, &g2, &y));
         });
     }
 
     // test and benchmarks for RSA
 
+    fn test_split<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
+        let mut rand = RandState::new();
+        let g1 = G::unknown_possibly_random_order_elem(&mut rand);
+        let g2 = G::unknown_possibly_random_order_elem(&mut rand);
+
+        let x1 = random_order::<G>(&params);
+        let x2 = random_order::<G>(&params);
+
+        let y = G::op(&G::exp(&g1, &x1), &G::exp(&g2, &x2));
+
+        let (pi, Q) = ZKPokRep::<G>::new(&params, &g1, &g2, &y, &x1, &x2).split();
+        let (T, p) = pi.root(&params, &g1, &g2, &y).unwrap();
+
+        assert_eq!(G::exp(&Q, &p), T);
+    }
+
+    fn test_factors<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
+        let mut rand = RandState::new();
+        let g1 = G::unknown_possibly_random_order_elem(&mut rand);
+        let g2 = G::unknown_possibly_random_order_elem(&mut rand);
+
+        let x1: Vec<Integer> = (0..50).map(|_| random_order::<G>(&params)).collect();
+        let x2 = -random_order::<G>(&params);
+
+        let mut prod = Integer::from(1);
+        for x in x1.iter() {
+            prod *= x;
+        }
+        let y = G::op(&G::exp(&g1, &prod), &G::exp(&g2, &x2));
+
+        let pi = ZKPokRep::<G>::new_factors(&params, &g1, &g2, &y, x1, &x2);
+        assert!(pi.verify(&params, &g1, &g2, &y));
+    }
+
+    fn test_oversized<G: ConvertibleUnknownOrderGroup>() {
+        let params = SecurityParameters::for_group::<G>();
+        let mut rand = RandState::new();
+        let g1 = G::unknown_possibly_random_order_elem(&mut rand);
+        let g2 = G::unknown_possibly_random_order_elem(&mut rand);
+
+        let x1 = random_order::<G>(&params);
+        let x2 = random_order::<G>(&params);
+        let y = G::op(&G::exp(&g1, &x1), &G::exp(&g2, &x2));
+
+        // shift k p from the quotient into r1, the equation still holds
+        let mut pi = ZKPokRep::<G>::new(&params, &g1, &g2, &y, &x1, &x2);
+        let (_, p) = challenge::<G>(&params, &g1, &g2, &y, &pi.a);
+        let k = Integer::from(1) << 4096;
+        pi.Q = G::op(&pi.Q, &G::exp(&g1, &-k.clone()));
+        pi.r1 = (Integer::from(pi.r1.as_ref()) + k * &p).into();
+        assert!(!pi.verify(&params, &g1, &g2, &y));
+
+        let (root, _) = pi.split();
+        assert!(root.root(&params, &g1, &g2, &y).is_none());
+    }
+
+    // total variation distance between the top bits of the responses c x + mask for the witness x
+    // (with the masks sampled as by `new_factors`) and those of a mask alone (the simulator)
+    fn response_distance<G: ConvertibleUnknownOrderGroup>(
+        params: &SecurityParameters,
+        x: &Integer,
+    ) -> f64 {
+        const SAMPLES: usize = 200_000;
+        const TOP: u32 = 4;
+
+        // buckets 0..2^TOP, the last one counts responses beyond the mask range
+        let bits = mask_bits::<G>(params, x.significant_bits());
+        let bucket = |z: Integer| (z >> (bits - TOP)).to_usize().unwrap().min(1 << TOP);
+        let mut real = vec![0i64; (1 << TOP) + 1];
+        let mut simulated = vec![0i64; (1 << TOP) + 1];
+        for _ in 0..SAMPLES {
+            let c = random_bits(params.challenge_bits as u32);
+            real[bucket(c * x + mask::<G>(params, x))] += 1;
+            simulated[bucket(random_bits(bits))] += 1;
+        }
+
+        let diff: i64 = real
+            .iter()
+            .zip(simulated.iter())
+            .map(|(r, s)| (r - s).abs())
+            .sum();
+        diff as f64 / (2 * SAMPLES) as f64
+    }
+
+    #[test]
+    fn test_statistical_zk() {
+        // the largest witness of the order size and a small one
+        let large = (Integer::from(1) << order_bits::<Rsa2048>()) - 1;
+        let small = Integer::from(1);
+
+        // the distance is at most 2^-zk_bits, the sampling error of the estimate about 0.005
+        let params = SecurityParameters::for_group::<Rsa2048>();
+        assert!(response_distance::<Rsa2048>(&params, &large) < 0.015);
+        assert!(response_distance::<Rsa2048>(&params, &small) < 0.015);
+
+        // with little slack the distance (about 2^-(zk_bits + 1) = 0.06) is detected
+        let mut params = SecurityParameters::for_group::<Rsa2048>();
+        params.zk_bits = 3;
+        assert!(response_distance::<Rsa2048>(&params, &large) > 0.03);
+    }
+
+    #[test]
+    fn test_invalid_elem_rsa() {
+        let params = SecurityParameters::for_group::<Rsa2048>();
+        let mut rand = RandState::new();
+        let g1 = Rsa2048::unknown_possibly_random_order_elem(&mut rand);
+        let g2 = Rsa2048::unknown_possibly_random_order_elem(&mut rand);
+
+        let x1 = random_order::<Rsa2048>(&params);
+        let x2 = random_order::<Rsa2048>(&params);
+        let y = Rsa2048::op(&Rsa2048::exp(&g1, &x1), &Rsa2048::exp(&g2, &x2));
+        let pi = ZKPokRep::<Rsa2048>::new(&params, &g1, &g2, &y, &x1, &x2);
+
+        // N - 2 is the same coset as 2, but not its canonical representative
+        let n = Rsa2048::rsa_modulus().unwrap();
+        let raw: BinInteger = (n - 2).into();
+        let bad: <Rsa2048 as Group>::Elem =
+            bincode::deserialize(&bincode::serialize(&raw).unwrap()).unwrap();
+        assert!(Rsa2048::validate(&bad).is_err());
+
+        let pi = ZKPokRep::<Rsa2048> {
+            a: pi.a,
+            Q: bad.clone(),
+            r1: pi.r1,
+            r2: pi.r2,
+        };
+        assert!(!pi.verify(&params, &g1, &g2, &y));
+
+        let (mut root, _) = pi.split();
+        root.a = bad;
+        assert!(root.root(&params, &g1, &g2, &y).is_none());
+    }
+
+    #[test]
+    fn test_oversized_rsa() {
+        test_oversized::<Rsa2048>();
+    }
+
+    #[test]
+    fn test_oversized_classgroup() {
+        test_oversized::<ClassGroup>();
+    }
+
+    #[test]
+    fn test_factors_rsa() {
+        test_factors::<Rsa2048>();
+    }
+
+    #[test]
+    fn test_factors_classgroup() {
+        test_factors::<ClassGroup>();
+    }
+
+    #[test]
+    fn test_split_rsa() {
+        test_split::<Rsa2048>();
+    }
+
+    #[test]
+    fn test_split_classgroup() {
+        test_split::<ClassGroup>();
+    }
+
     #[test]
     fn test_mult_proof_rsa() {
         test_mult_proof::<Rsa2048>();
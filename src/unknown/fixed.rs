@@ -0,0 +1,159 @@
+use cpsnarks_set::utils::ConvertibleUnknownOrderGroup;
+
+use rug::Integer;
+
+// bits per window of the precomputed table
+const WINDOW: u32 = 4;
+
+/// Precomputed powers of a fixed base,
+/// exponentiation then takes one group operation per window (and no squarings).
+pub struct FixedBase<G: ConvertibleUnknownOrderGroup> {
+    base: G::Elem,
+    bits: u32,                // exponents up to this many bits are covered by the table
+    table: Vec<Vec<G::Elem>>, // table[i][d - 1] = base^(d * 2^(WINDOW * i))
+    top: G::Elem,             // base^(2^bits), for exponents exceeding the table
+}
+
+impl<G: ConvertibleUnknownOrderGroup> FixedBase<G> {
+    pub fn new(base: &G::Elem, bits: u32) -> Self {
+        let windows = (bits + WINDOW - 1) / WINDOW;
+        let mut table = Vec::with_capacity(windows as usize);
+        let mut power = base.clone(); // base^(2^(WINDOW * i))
+        for _ in 0..windows {
+            let mut row = vec![power.clone()];
+            for _ in 2..(1 << WINDOW) {
+                let next = G::op(&row[row.len() - 1], &power);
+                row.push(next);
+            }
+            power = G::op(&row[row.len() - 1], &power);
+            table.push(row);
+        }
+        FixedBase {
+            base: base.clone(),
+            bits: windows * WINDOW,
+            table,
+            top: power,
+        }
+    }
+
+    /// Covers all exponents below the order bound of the group
+    pub fn with_order_bound(base: &G::Elem) -> Self {
+        Self::new(base, G::order_upper_bound().significant_bits())
+    }
+
+    pub fn base(&self) -> &G::Elem {
+        &self.base
+    }
+
+    pub fn exp(&self, exp: &Integer) -> G::Elem {
+        if *exp < 0 {
+            return G::inv(&self.exp(&Integer::from(-exp)));
+        }
+
+        // sum of one table entry per window
+        let mut res: Option<G::Elem> = None;
+        for (i, row) in self.table.iter().enumerate() {
+            let mut d = 0;
+            for j in (0..WINDOW).rev() {
+                d = (d << 1) | exp.get_bit(i as u32 * WINDOW + j) as usize;
+            }
+            if d != 0 {
+                res = Some(match res {
+                    None => row[d - 1].clone(),
+                    Some(res) => G::op(&res, &row[d - 1]),
+                });
+            }
+        }
+        let res = res.unwrap_or_else(G::id);
+
+        // exponent exceeds the table
+        if exp.significant_bits() > self.bits {
+            let high = Integer::from(exp >> self.bits);
+            return G::op(&res, &G::exp(&self.top, &high));
+        }
+        res
+    }
+
+    /// Exponentiation by a secret: the table lookups above are indexed by the exponent,
+    /// so this falls back to the group's side-channel resistant exponentiation of the base.
+    pub fn exp_secret(&self, exp: &Integer) -> G::Elem {
+        G::exp_secret(&self.base, exp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::unknown::{order_bits, random_bits};
+
+    use accumulator::group::{ClassGroup, Rsa3072};
+
+    use rug::rand::RandState;
+
+    use test::Bencher;
+
+    fn test_exp<G: ConvertibleUnknownOrderGroup>() {
+        let mut rand = RandState::new();
+        let base = G::unknown_possibly_random_order_elem(&mut rand);
+        let fixed = FixedBase::<G>::with_order_bound(&base);
+
+        for exp in [
+            Integer::from(0),
+            Integer::from(1),
+            Integer::from(17),
+            random_bits(order_bits::<G>()),
+            -random_bits(order_bits::<G>()),
+            random_bits(order_bits::<G>()) * random_bits(order_bits::<G>()), // exceeds the table
+        ]
+        .iter()
+        {
+            assert_eq!(fixed.exp(exp), G::exp(&base, exp));
+        }
+    }
+
+    #[test]
+    fn test_exp_rsa() {
+        test_exp::<Rsa3072>();
+    }
+
+    #[test]
+    fn test_exp_classgroup() {
+        test_exp::<ClassGroup>();
+    }
+
+    fn bench_fixed<G: ConvertibleUnknownOrderGroup>(b: &mut Bencher) {
+        let mut rand = RandState::new();
+        let base = G::unknown_possibly_random_order_elem(&mut rand);
+        let fixed = FixedBase::<G>::with_order_bound(&base);
+        let exp = random_bits(order_bits::<G>());
+        b.iter(|| fixed.exp(&exp))
+    }
+
+    fn bench_plain<G: ConvertibleUnknownOrderGroup>(b: &mut Bencher) {
+        let mut rand = RandState::new();
+        let base = G::unknown_possibly_random_order_elem(&mut rand);
+        let exp = random_bits(order_bits::<G>());
+        b.iter(|| G::exp(&base, &exp))
+    }
+
+    #[bench]
+    fn bench_fixed_rsa(b: &mut Bencher) {
+        bench_fixed::<Rsa3072>(b);
+    }
+
+    #[bench]
+    fn bench_plain_rsa(b: &mut Bencher) {
+        bench_plain::<Rsa3072>(b);
+    }
+
+    #[bench]
+    fn bench_fixed_classgroup(b: &mut Bencher) {
+        bench_fixed::<ClassGroup>(b);
+    }
+
+    #[bench]
+    fn bench_plain_classgroup(b: &mut Bencher) {
+        bench_plain::<ClassGroup>(b);
+    }
+}
@@ -1,6 +1,10 @@
 pub mod base;
 pub mod exppok;
 pub mod extend;
+pub mod fixed;
+
+#[allow(non_snake_case)]
+pub(crate) mod reduce;
 
 use cpsnarks_set::utils::ConvertibleUnknownOrderGroup;
 use rand_core::{OsRng, RngCore};
@@ -9,6 +13,7 @@ use rug::rand::RandState;
 use rug::Integer;
 
 use crate::bytes_to_integer;
+use crate::params::SecurityParameters;
 
 pub fn random_bound(upper: &Integer) -> Integer {
     let size = (upper.significant_bits() + 7) / 8;
@@ -17,8 +22,30 @@ pub fn random_bound(upper: &Integer) -> Integer {
     bytes_to_integer(&bytes[..])
 }
 
-pub fn random_order<G: ConvertibleUnknownOrderGroup>() -> Integer {
-    random_bound(&G::order_upper_bound())
+/// Uniform in [0, 2^bits)
+pub fn random_bits(bits: u32) -> Integer {
+    let mut bytes = vec![0; ((bits + 7) / 8) as usize];
+    OsRng.fill_bytes(&mut bytes);
+    let mut x = bytes_to_integer(&bytes[..]);
+    x.keep_bits_mut(bits);
+    x
+}
+
+/// Randomness of Fujisaki-Okamoto commitments: zk_bits beyond the order bound,
+/// such that it is statistically close to uniform modulo the (unknown) order.
+pub fn random_order<G: ConvertibleUnknownOrderGroup>(params: &SecurityParameters) -> Integer {
+    random_bits(order_bits::<G>() + params.zk_bits as u32)
+}
+
+/// Bit length of the order bound
+pub fn order_bits<G: ConvertibleUnknownOrderGroup>() -> u32 {
+    G::order_upper_bound().significant_bits()
+}
+
+/// Checks |x| < 2^bits, verifiers call this on every integer sent by the prover
+/// before it is used as an exponent.
+pub fn bounded(x: &Integer, bits: u32) -> bool {
+    x.significant_bits() <= bits
 }
 
 pub fn new_fujisaki_okamoto_gens<G: ConvertibleUnknownOrderGroup>() -> (G::Elem, G::Elem) {
@@ -26,8 +53,63 @@ pub fn new_fujisaki_okamoto_gens<G: ConvertibleUnknownOrderGroup>() -> (G::Elem,
     let g = G::unknown_possibly_random_order_elem(&mut rand);
     let mut h = G::unknown_possibly_random_order_elem(&mut rand);
     if g == h {
-        let r = random_order::<G>();
+        let r = random_bound(&G::order_upper_bound());
         h = G::exp(&h, &r);
     }
     (g, h)
 }
+
+/// Runs both closures, in parallel if the "parallel" feature is enabled
+pub(crate) fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        rayon::join(a, b)
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        (a(), b())
+    }
+}
+
+/// Computes f(0), ..., f(n - 1), in parallel if the "parallel" feature is enabled
+pub(crate) fn par_map<T, F>(n: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Send + Sync,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        (0..n).into_par_iter().map(f).collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        (0..n).map(f).collect()
+    }
+}
+
+/// Checks f(0), ..., f(n - 1), in parallel if the "parallel" feature is enabled,
+/// stops at the first failure
+pub(crate) fn par_all<F>(n: usize, f: F) -> bool
+where
+    F: Fn(usize) -> bool + Send + Sync,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        (0..n).into_par_iter().all(f)
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        (0..n).all(f)
+    }
+}
@@ -0,0 +1,343 @@
+use accumulator::group::Group;
+use rand_core::{OsRng, RngCore};
+
+use rug::Integer;
+
+use super::join;
+
+use std::ops::Mul;
+
+// smallest product tree split across threads
+const PARALLEL_PRODUCT: usize = 256;
+
+// Everything reduced is public: the factors of products (ring keys and challenges) and the mask
+// of the public dlog of ZKPokRep, so the quotients are exponentiated with `exp`.
+pub(crate) trait Reducible<G: Group> {
+    // reduce equation modulo p
+    fn reduce(&self, base: &G::Elem, p: &Integer) -> (G::Elem, Integer);
+}
+
+pub(crate) struct ReducibleProduct<G: Group> {
+    reduc: Box<dyn Reducible<G>>,
+    small: Integer,
+}
+
+pub(crate) struct ReducibleSum<G: Group> {
+    left: Box<dyn Reducible<G>>,
+    right: Box<dyn Reducible<G>>,
+}
+
+impl<G: Group> Reducible<G> for ReducibleProduct<G> {
+    fn reduce(&self, base: &G::Elem, p: &Integer) -> (G::Elem, Integer) {
+        // G^q0, r0 : n0 = q0 * p + r0
+        let (Q0, r0) = self.reduc.reduce(base, p);
+
+        //   q1, r1 : n1 = q1 * p + r1
+        let (q1, r1) = self.small.clone().div_rem(p.clone());
+
+        // compute Q0^(n1) = G^{p n1} = G^{q0 q1 p + q0 r1}
+        let Q0_q1p = G::exp(&Q0, &self.small);
+
+        // multiply remainders
+        let r0r1: Integer = (&r0 * &r1).into();
+        let (div, rem) = r0r1.div_rem(p.clone());
+
+        // compute G^{q1 * r0 + div}
+        let q1r0_div = q1 * r0 + div;
+        let G_r1 = G::exp(base, &q1r0_div);
+
+        (G::op(&Q0_q1p, &G_r1), rem)
+    }
+}
+
+impl<G: Group> Mul<Integer> for Box<dyn Reducible<G>> {
+    type Output = Box<ReducibleProduct<G>>;
+
+    fn mul(self, rhs: Integer) -> Self::Output {
+        Box::new(ReducibleProduct {
+            reduc: self,
+            small: rhs,
+        })
+    }
+}
+
+pub(crate) fn mul_reducible<G: Group>(
+    reduc: Box<dyn Reducible<G>>,
+    small: Integer,
+) -> Box<ReducibleProduct<G>> {
+    Box::new(ReducibleProduct { reduc, small })
+}
+
+/// Like `product`, but consumes the factors one at a time:
+/// keeps a stack of subtree products, merging subtrees of equal size.
+pub(crate) fn product_iter<I: Iterator<Item = Integer>>(factors: I) -> Integer {
+    let mut stack: Vec<(u32, Integer)> = vec![];
+    for factor in factors {
+        let mut node = (0, factor);
+        while let Some((level, _)) = stack.last() {
+            if *level != node.0 {
+                break;
+            }
+            let (level, left) = stack.pop().unwrap();
+            node = (level + 1, left * node.1);
+        }
+        stack.push(node);
+    }
+    stack
+        .into_iter()
+        .rev()
+        .fold(Integer::from(1), |acc, (_, value)| acc * value)
+}
+
+pub(crate) fn add_reducible<G: Group>(
+    left: Box<dyn Reducible<G>>,
+    right: Box<dyn Reducible<G>>,
+) -> Box<ReducibleSum<G>> {
+    Box::new(ReducibleSum { left, right })
+}
+
+/// Computes the product using a balanced product tree
+/// (subtrees are multiplied in parallel if the "parallel" feature is enabled)
+pub(crate) fn product(factors: &[Integer]) -> Integer {
+    match factors.len() {
+        0 => Integer::from(1),
+        1 => factors[0].clone(),
+        n if n < PARALLEL_PRODUCT => product(&factors[..n / 2]) * product(&factors[n / 2..]),
+        n => {
+            let (left, right) = join(|| product(&factors[..n / 2]), || product(&factors[n / 2..]));
+            left * right
+        }
+    }
+}
+
+impl<G: Group> Reducible<G> for ReducibleSum<G> {
+    fn reduce(&self, base: &G::Elem, p: &Integer) -> (G::Elem, Integer) {
+        // G^q0, r0 : n0 = q0 * p + r0
+        let (Q0, r0) = self.left.reduce(base, p);
+
+        // G^q0, r0 : n0 = q0 * p + r0
+        let (Q1, r1) = self.right.reduce(base, p);
+
+        // add remainders
+        let (div, rem) = (r0 + r1).div_rem(p.clone());
+
+        // quotient computation
+        let G_div = G::exp(base, &div);
+        let Q0_Q1 = G::op(&Q0, &Q1);
+        (G::op(&G_div, &Q0_Q1), rem)
+    }
+}
+
+impl<G: Group> Reducible<G> for Integer {
+    fn reduce(&self, base: &G::Elem, p: &Integer) -> (G::Elem, Integer) {
+        let (qou, rem) = self.clone().div_rem(p.clone());
+        (G::exp(base, &qou), rem)
+    }
+}
+
+// product of small factors: like a chain of ReducibleProduct, but without recursion
+impl<G: Group> Reducible<G> for Vec<Integer> {
+    fn reduce(&self, base: &G::Elem, p: &Integer) -> (G::Elem, Integer) {
+        let mut Q = G::id();
+        let mut r = Integer::from(1).div_rem(p.clone()).1;
+        for small in self.iter() {
+            //   q1, r1 : n1 = q1 * p + r1
+            let (q1, r1) = small.clone().div_rem(p.clone());
+
+            // multiply remainders
+            let (div, rem) = Integer::from(&r * &r1).div_rem(p.clone());
+
+            // compute Q^(n1) * G^{q1 * r0 + div}
+            let q1r0_div = q1 * &r + div;
+            Q = G::op(&G::exp(&Q, small), &G::exp(base, &q1r0_div));
+            r = rem;
+        }
+        (Q, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bytes_to_integer;
+
+    use test::Bencher;
+
+    use accumulator::group::Rsa2048;
+    use accumulator::group::UnknownOrderGroup;
+
+    fn random_integer(magnitude: usize) -> Integer {
+        let mut bytes = vec![0; magnitude];
+        OsRng.fill_bytes(&mut bytes);
+        bytes_to_integer(&bytes[..])
+    }
+
+    fn elems(len: usize, magnitude: usize) -> Vec<Integer> {
+        let mut elems: Vec<Integer> = Vec::with_capacity(len);
+        for _ in 0..len {
+            elems.push(random_integer(magnitude));
+        }
+        elems
+    }
+
+    #[bench]
+    fn bench_reduce_mul_reduce(b: &mut Bencher) {
+        let elems = elems(2000, 32);
+        let prime = random_integer(16);
+        let base = Rsa2048::unknown_order_elem();
+        b.iter(|| {
+            let mut m: Box<dyn Reducible<Rsa2048>> = Box::new(elems[0].clone());
+            for v in elems[1..].iter() {
+                m = mul_reducible::<Rsa2048>(m, v.clone());
+            }
+            m.reduce(&base, &prime);
+        })
+    }
+
+    #[bench]
+    fn bench_reduce_mul_naive(b: &mut Bencher) {
+        let elems = elems(2000, 32);
+        let prime = random_integer(16);
+        let base = Rsa2048::unknown_order_elem();
+        b.iter(|| {
+            let mut res = Integer::from(1);
+            for v in elems.iter() {
+                res = res * v;
+            }
+            let (div, _) = res.div_rem(prime.clone());
+            Rsa2048::exp(&base, &div);
+        })
+    }
+
+    fn bench_reduce_factors(b: &mut Bencher, n: usize) {
+        let elems = elems(n, 32);
+        let prime = random_integer(16).next_prime();
+        let base = Rsa2048::unknown_order_elem();
+        b.iter(|| Reducible::<Rsa2048>::reduce(&elems, &base, &prime))
+    }
+
+    fn bench_reduce_naive(b: &mut Bencher, n: usize) {
+        let elems = elems(n, 32);
+        let prime = random_integer(16).next_prime();
+        let base = Rsa2048::unknown_order_elem();
+        b.iter(|| {
+            let mut res = Integer::from(1);
+            for v in elems.iter() {
+                res = res * v;
+            }
+            let (div, _) = res.div_rem(prime.clone());
+            Rsa2048::exp(&base, &div);
+        })
+    }
+
+    #[bench]
+    fn bench_reduce_factors_10k(b: &mut Bencher) {
+        bench_reduce_factors(b, 10_000);
+    }
+
+    #[bench]
+    fn bench_reduce_factors_100k(b: &mut Bencher) {
+        bench_reduce_factors(b, 100_000);
+    }
+
+    #[bench]
+    fn bench_reduce_factors_1m(b: &mut Bencher) {
+        bench_reduce_factors(b, 1_000_000);
+    }
+
+    #[bench]
+    fn bench_reduce_naive_10k(b: &mut Bencher) {
+        bench_reduce_naive(b, 10_000);
+    }
+
+    #[bench]
+    fn bench_reduce_naive_100k(b: &mut Bencher) {
+        bench_reduce_naive(b, 100_000);
+    }
+
+    #[bench]
+    fn bench_reduce_naive_1m(b: &mut Bencher) {
+        bench_reduce_naive(b, 1_000_000);
+    }
+
+    #[test]
+    fn test_product() {
+        let elems = elems(1001, 32);
+        let mut res = Integer::from(1);
+        for v in elems.iter() {
+            res = res * v;
+        }
+        assert_eq!(product(&elems[..]), res);
+        assert_eq!(product_iter(elems.iter().cloned()), res);
+        assert_eq!(product(&[]), Integer::from(1));
+    }
+
+    #[test]
+    fn test_reduce_factors() {
+        let p = random_integer(16).next_prime();
+        let elems = elems(100, 32);
+        let base = Rsa2048::unknown_order_elem();
+
+        let (Q, rem) = Reducible::<Rsa2048>::reduce(&elems, &base, &p);
+
+        let (div, rem2) = product(&elems[..]).div_rem(p.clone());
+        assert_eq!(Q, Rsa2048::exp(&base, &div));
+        assert_eq!(rem, rem2);
+    }
+
+    #[test]
+    fn test_reduce_sum() {
+        let p = random_integer(16).next_prime();
+        let elems = elems(10, 32);
+        let c = random_integer(16);
+        let a = random_integer(300);
+        let base = Rsa2048::unknown_order_elem();
+
+        // c * prod + a
+        let m: Box<dyn Reducible<Rsa2048>> = add_reducible::<Rsa2048>(
+            mul_reducible::<Rsa2048>(Box::new(elems.clone()), c.clone()),
+            Box::new(a.clone()),
+        );
+        let (Q, rem) = m.reduce(&base, &p);
+
+        let (div, rem2) = (c * product(&elems[..]) + a).div_rem(p.clone());
+        assert_eq!(Q, Rsa2048::exp(&base, &div));
+        assert_eq!(rem, rem2);
+    }
+
+    #[test]
+    fn test_reduce_mul() {
+        let p = Integer::from(0x4621).next_prime();
+
+        let elems = vec![
+            Integer::from(0x54333234),
+            Integer::from(0x4a888b6c),
+            Integer::from(0x6276e54b),
+            Integer::from(0x6275454a),
+            Integer::from(0x6275454a) * Integer::from(0x7355_3254) * Integer::from(0x7355_3254),
+            Integer::from(0x4a888b6c),
+            Integer::from(0x6276e54b),
+            Integer::from(0x6275454a),
+        ];
+
+        // create reducible product
+        let mut m: Box<dyn Reducible<Rsa2048>> = Box::new(elems[0].clone());
+        for v in elems[1..].iter() {
+            m = mul_reducible::<Rsa2048>(m, v.clone());
+        }
+        let base = Rsa2048::unknown_order_elem();
+        let (Q1, rem1) = m.reduce(&base, &p);
+
+        // compute base truth
+        let mut res = Integer::from(1);
+        for v in elems.iter() {
+            res = res * v;
+        }
+        let (div2, rem2) = res.div_rem(p.clone());
+
+        // compare
+        assert_eq!(Q1, Rsa2048::exp(&base, &div2));
+        assert_eq!(rem1, rem2);
+    }
+}